@@ -0,0 +1,218 @@
+use crate::api::Station;
+use crate::backend::Backend;
+use crate::config::{read_alarms, write_alarms, Config};
+use crate::player::Player;
+use crate::timer::FadeIn;
+use crate::tools::pause;
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::process::exit;
+use std::thread;
+use std::time::Duration;
+
+/// Longest sleep between two reads of the alarms file while waiting
+const ALARM_CHECK: Duration = Duration::from_secs(30);
+
+/**
+Start a station at a given time, once or on some days of the week
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Alarm {
+    pub id: u32,
+    /// Prefix of the station to play
+    pub station: String,
+    pub time: NaiveTime,
+    /// Days the alarm rings, empty for a one-off alarm
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Day of a one-off alarm
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+}
+
+impl Alarm {
+    /**
+    New alarm with an id not used by the others. Without days it rings once, at the next occurrence of the time
+     */
+    pub fn new(alarms: &[Alarm], station: String, time: NaiveTime, days: Vec<Weekday>) -> Self {
+        let id = alarms.iter().map(|alarm| alarm.id).max().unwrap_or(0) + 1;
+        let date = if days.is_empty() {
+            let now = Local::now();
+            let today = now.date_naive();
+            Some(if time > now.time() {
+                today
+            } else {
+                today + Days::new(1)
+            })
+        } else {
+            None
+        };
+        Self {
+            id,
+            station,
+            time,
+            days,
+            date,
+        }
+    }
+
+    pub fn is_recurring(&self) -> bool {
+        self.date.is_none()
+    }
+
+    /**
+    Next time the alarm rings after the given moment, None for a passed one-off alarm
+     */
+    pub fn next(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let dates: Vec<NaiveDate> = match self.date {
+            Some(date) => vec![date],
+            None => (0..=7)
+                .map(|days| after.date_naive() + Days::new(days))
+                .filter(|date| self.days.contains(&date.weekday()))
+                .collect(),
+        };
+        dates
+            .into_iter()
+            .filter_map(|date| {
+                date.and_time(self.time)
+                    .and_local_timezone(Local)
+                    .earliest()
+            })
+            .find(|at| *at > after)
+    }
+}
+
+impl Display for Alarm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let when = match self.date {
+            Some(date) => date.format("%Y-%m-%d").to_string(),
+            None => self
+                .days
+                .iter()
+                .map(|day| day.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+        write!(
+            f,
+            "#{} {} {} {}",
+            self.id,
+            self.time.format("%H:%M"),
+            when,
+            self.station
+        )
+    }
+}
+
+/**
+Parse a time written as `HH:MM`
+ */
+pub fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("invalid time: {}", value))
+}
+
+/**
+Parse a list of days like `mon,wed,fri`, or one of `daily`, `weekdays` and `weekend`
+ */
+pub fn parse_days(value: &str) -> Result<Vec<Weekday>, String> {
+    use Weekday::*;
+    match value {
+        "daily" => Ok(vec![Mon, Tue, Wed, Thu, Fri, Sat, Sun]),
+        "weekdays" => Ok(vec![Mon, Tue, Wed, Thu, Fri]),
+        "weekend" => Ok(vec![Sat, Sun]),
+        _ => value
+            .split(',')
+            .map(|day| {
+                day.trim()
+                    .parse::<Weekday>()
+                    .map_err(|_| format!("invalid day: {}", day))
+            })
+            .collect(),
+    }
+}
+
+/**
+The alarm ringing first after the given moment
+ */
+pub fn next_alarm(alarms: &[Alarm], after: DateTime<Local>) -> Option<(&Alarm, DateTime<Local>)> {
+    alarms
+        .iter()
+        .filter_map(|alarm| alarm.next(after).map(|at| (alarm, at)))
+        .min_by_key(|(_, at)| *at)
+}
+
+/**
+An alarm which rang between the two moments
+ */
+pub fn due_alarm(alarms: &[Alarm], since: DateTime<Local>, now: DateTime<Local>) -> Option<&Alarm> {
+    next_alarm(alarms, since)
+        .filter(|(_, at)| *at <= now)
+        .map(|(alarm, _)| alarm)
+}
+
+/**
+Forget a one-off alarm once it rang
+ */
+pub fn alarm_rang(alarm: &Alarm) {
+    if alarm.is_recurring() {
+        return;
+    }
+    if let Ok(mut alarms) = read_alarms() {
+        alarms.retain(|a| a.id != alarm.id);
+        let _ = write_alarms(&alarms);
+    }
+}
+
+/**
+Sleep until the next alarm then play its station, raising the volume progressively
+ */
+pub fn wait_alarm(list: &[Station], config: &Config, backend: Backend) -> color_eyre::Result<()> {
+    let mut announced = None;
+    let alarm = loop {
+        let alarms = read_alarms()?;
+        let now = Local::now();
+        let (alarm, at) = match next_alarm(&alarms, now) {
+            Some(next) => next,
+            None => {
+                eprintln!("No alarm scheduled");
+                exit(1);
+            }
+        };
+        if announced != Some(at) {
+            println!("Next alarm : {} at {}", alarm, at.format("%Y-%m-%d %H:%M"));
+            announced = Some(at);
+        }
+
+        let wait = (at - now).to_std().unwrap_or_default();
+        if wait > ALARM_CHECK {
+            thread::sleep(ALARM_CHECK);
+            continue;
+        }
+        thread::sleep(wait);
+        break alarm.clone();
+    };
+    alarm_rang(&alarm);
+
+    let station = match list.iter().find(|s| s.prefix == alarm.station) {
+        Some(station) => station,
+        None => {
+            eprintln!("Station not found : {}", alarm.station);
+            exit(1);
+        }
+    };
+    let url = station.stream_url(backend.playable(config.quality));
+    let mut player = Player::new(url.to_string(), config, backend);
+    player.set_volume(0);
+    player.play(url);
+    println!("Now playing : {}", station.title);
+
+    let fade = FadeIn::new(config.volume);
+    while !fade.is_over() {
+        player.set_volume(fade.volume());
+        thread::sleep(Duration::from_secs(1));
+    }
+    player.set_volume(config.volume);
+    pause();
+    Ok(())
+}
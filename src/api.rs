@@ -1,22 +1,113 @@
 use curl::easy::Easy;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::error::Category;
+use std::collections::HashMap;
+use std::env;
 use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+const DEFAULT_API_URL: &str = "https://www.radiorecord.ru/api";
+/// Environment variable overriding the api base url
+pub const API_URL_ENV: &str = "RRT_API_URL";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
 /**
 Represent a song (title and artist)
 */
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Title {
     pub song: String,
     pub artist: String,
 }
 
+impl Title {
+    /**
+    Read a title sent in the stream metadata, written as `artist - song`
+     */
+    pub fn from_stream_title(value: &str) -> Option<Self> {
+        let (artist, song) = value.split_once(" - ")?;
+        Some(Self {
+            song: song.trim().to_string(),
+            artist: artist.trim().to_string(),
+        })
+    }
+}
+
 impl Display for Title {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} - {}", self.song, self.artist)
     }
 }
 /**
+Represent a music genre a station belongs to
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct Genre {
+    pub id: usize,
+    pub name: String,
+}
+/**
+Stream variants provided for each station
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+    /// 64 kbps
+    Low,
+    /// 128 kbps
+    Medium,
+    /// 320 kbps
+    #[default]
+    High,
+    /// HTTP live streaming
+    Hls,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 4] = [Quality::Low, Quality::Medium, Quality::High, Quality::Hls];
+
+    /**
+    Next quality in the toggle order
+     */
+    pub fn next(self) -> Self {
+        match self {
+            Quality::High => Quality::Medium,
+            Quality::Medium => Quality::Low,
+            Quality::Low => Quality::Hls,
+            Quality::Hls => Quality::High,
+        }
+    }
+
+    /**
+    Qualities to try in order when this one is preferred
+     */
+    fn fallbacks(self) -> [Quality; 4] {
+        match self {
+            Quality::High => [Quality::High, Quality::Medium, Quality::Low, Quality::Hls],
+            Quality::Medium => [Quality::Medium, Quality::Low, Quality::High, Quality::Hls],
+            Quality::Low => [Quality::Low, Quality::Medium, Quality::High, Quality::Hls],
+            Quality::Hls => [Quality::Hls, Quality::High, Quality::Medium, Quality::Low],
+        }
+    }
+}
+
+impl Display for Quality {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Quality::Low => "64k",
+            Quality::Medium => "128k",
+            Quality::High => "320k",
+            Quality::Hls => "HLS",
+        };
+        write!(f, "{}", name)
+    }
+}
+/**
 Represent a station with useful info
  */
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -27,7 +118,44 @@ pub struct Station {
     pub tooltip: String,
     short_title: String,
     pub icon_fill_white: String,
+    #[serde(default)]
+    pub stream_64: String,
+    #[serde(default)]
+    pub stream_128: String,
     pub stream_320: String,
+    #[serde(default)]
+    pub stream_hls: String,
+    #[serde(default)]
+    pub genre: Vec<Genre>,
+}
+
+impl Station {
+    /**
+    Url of the stream in the given quality. Fall back to another quality if it is missing
+     */
+    pub fn stream_url(&self, quality: Quality) -> &str {
+        self.stream(self.stream_quality(quality))
+    }
+
+    /**
+    Quality really streamed when the given one is preferred, the first fallback the station provides
+     */
+    pub fn stream_quality(&self, quality: Quality) -> Quality {
+        quality
+            .fallbacks()
+            .into_iter()
+            .find(|quality| !self.stream(*quality).is_empty())
+            .unwrap_or(Quality::High)
+    }
+
+    fn stream(&self, quality: Quality) -> &str {
+        match quality {
+            Quality::Low => &self.stream_64,
+            Quality::Medium => &self.stream_128,
+            Quality::High => &self.stream_320,
+            Quality::Hls => &self.stream_hls,
+        }
+    }
 }
 
 impl PartialEq for Station {
@@ -37,9 +165,22 @@ impl PartialEq for Station {
 }
 impl Eq for Station {}
 
+/**
+All the genres of the stations sorted by name
+ */
+pub fn list_genres(stations: &[Station]) -> Vec<Genre> {
+    let mut genres: Vec<Genre> = stations
+        .iter()
+        .flat_map(|s| s.genre.iter().cloned())
+        .collect();
+    genres.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    genres.dedup();
+    genres
+}
+
 #[derive(Serialize, Deserialize)]
 struct Part {
-    genre: Value,
+    genre: Vec<Genre>,
     stations: Vec<Station>,
 }
 
@@ -68,86 +209,351 @@ struct PartNowPlaying {
 struct ResNowPlaying {
     result: Vec<PartNowPlaying>,
 }
-#[derive(Debug)]
+/**
+Everything that can go wrong while talking to the api
+ */
+#[derive(Error, Debug)]
 pub enum ApiError {
+    #[error("no connection available")]
     NoConnection,
-    ServerError,
+    #[error("the request timed out")]
+    Timeout,
+    #[error("the server answered with HTTP status {0}")]
+    HttpStatus(u32),
+    #[error("could not decode the response: {0}")]
+    Decode(String),
+    #[error("unexpected response format: {0}")]
+    Schema(serde_json::Error),
+    #[error("unknown station id {0}")]
+    UnknownStation(usize),
 }
 
 /**
-Fetch the list of stations and some information about them
+Read a JSON response. Bytes which are not JSON are a decode error, JSON of another shape a schema error
  */
-pub fn stations_list() -> Result<Vec<Station>, ApiError> {
-    let data = read("https://www.radiorecord.ru/api/stations/")?;
-
-    let str_ = std::str::from_utf8(&data).unwrap();
-    let json: Res = serde_json::from_str(str_).unwrap();
-
-    Ok(json.result.stations)
+fn parse<T: DeserializeOwned>(data: &[u8]) -> Result<T, ApiError> {
+    let str_ = std::str::from_utf8(data).map_err(|error| ApiError::Decode(error.to_string()))?;
+    serde_json::from_str(str_).map_err(|error| match error.classify() {
+        Category::Data => ApiError::Schema(error),
+        _ => ApiError::Decode(error.to_string()),
+    })
 }
+
 /**
-Fetch a list of the song and artist in the history of a station
-*/
-pub fn history(id: usize) -> Result<Vec<Title>, ApiError> {
-    let data = read(format!("https://www.radiorecord.ru/api/station/history/?id={}", id).as_str())?;
+Fetch the raw content behind an url
+ */
+pub trait Transport: Send + Sync {
+    fn get(&self, url: &str) -> Result<Vec<u8>, ApiError>;
+}
 
-    let str_ = std::str::from_utf8(&data).unwrap();
-    let json: ResHistory = match serde_json::from_str::<ResHistory>(str_) {
-        Ok(res) => res,
-        Err(_) => return Err(ApiError::ServerError),
-    };
+/**
+Default transport doing http requests with curl
+ */
+pub struct CurlTransport;
 
-    Ok(json.result.history)
+impl Transport for CurlTransport {
+    fn get(&self, url: &str) -> Result<Vec<u8>, ApiError> {
+        let mut data = Vec::new();
+        let mut handle = Easy::new();
+        handle.url(url).map_err(|_| ApiError::NoConnection)?;
+        handle
+            .timeout(REQUEST_TIMEOUT)
+            .map_err(|_| ApiError::NoConnection)?;
+        let res;
+        {
+            let mut transfer = handle.transfer();
+            transfer
+                .write_function(|new_data| {
+                    data.extend_from_slice(new_data);
+                    Ok(new_data.len())
+                })
+                .unwrap();
+            res = transfer.perform();
+        }
+        match res {
+            Ok(_) => match handle.response_code() {
+                Ok(code) if code >= 400 => Err(ApiError::HttpStatus(code)),
+                _ => Ok(data),
+            },
+            Err(error) if error.is_operation_timedout() => Err(ApiError::Timeout),
+            Err(_) => Err(ApiError::NoConnection),
+        }
+    }
 }
 
 /**
-Fetch the current playing song
-*/
-pub fn now_playing(id: usize) -> Result<Title, ApiError> {
-    let res = now_playing_back(id);
-    if res.is_ok() {
-        res
-    } else {
-        match history(id) {
-            Ok(mut vec) => Ok(vec.remove(0)),
-            Err(error) => Err(error),
+Transport serving recorded responses from a directory instead of the network.
+
+The endpoint path is mapped to a file name and the query is ignored,
+so `stations/now/` is read from `stations_now.json` and `station/history/?id=1` from `station_history.json`
+ */
+pub struct FixtureTransport {
+    base_url: String,
+    dir: PathBuf,
+}
+
+impl FixtureTransport {
+    pub fn new(base_url: &str, dir: PathBuf) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            dir,
         }
     }
+
+    fn fixture_name(&self, url: &str) -> String {
+        let path = url.strip_prefix(&self.base_url).unwrap_or(url);
+        let path = path.split('?').next().unwrap_or_default();
+        format!("{}.json", path.trim_matches('/').replace('/', "_"))
+    }
+}
+
+impl Transport for FixtureTransport {
+    fn get(&self, url: &str) -> Result<Vec<u8>, ApiError> {
+        let path = self.dir.join(self.fixture_name(url));
+        fs::read(path).map_err(|_| ApiError::HttpStatus(404))
+    }
 }
+
 /**
-Fetch the current playing song from the now endpoint
-*/
-fn now_playing_back(id: usize) -> Result<Title, ApiError> {
-    let data = read("https://www.radiorecord.ru/api/stations/now/")?;
+Client for the radio record api. Every endpoint is resolved against its base url.
 
-    let str_ = std::str::from_utf8(&data).unwrap();
-    let json: ResNowPlaying = serde_json::from_str(str_).unwrap();
+A `file://` base url serves the api from a directory of fixtures (see [`FixtureTransport`])
+ */
+#[derive(Clone)]
+pub struct ApiClient {
+    base_url: String,
+    transport: Arc<dyn Transport>,
+}
 
-    let station = json.result.into_iter().find(|x| x.id == id).unwrap();
+impl Default for ApiClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_API_URL)
+    }
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str) -> Self {
+        let transport: Arc<dyn Transport> = match base_url.strip_prefix("file://") {
+            Some(dir) => Arc::new(FixtureTransport::new(base_url, PathBuf::from(dir))),
+            None => Arc::new(CurlTransport),
+        };
+        Self::with_transport(base_url, transport)
+    }
+
+    pub fn with_transport(base_url: &str, transport: Arc<dyn Transport>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            transport,
+        }
+    }
+
+    /**
+    Pick the base url from the cli flag, then the environment and then the config file
+     */
+    pub fn resolve(cli_url: Option<String>, config_url: Option<String>) -> Self {
+        let url = cli_url
+            .or_else(|| env::var(API_URL_ENV).ok())
+            .or(config_url)
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+        Self::new(&url)
+    }
 
-    Ok(station.track)
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    /**
+    Fetch the list of stations and some information about them
+     */
+    pub fn stations_list(&self) -> Result<Vec<Station>, ApiError> {
+        let data = self.transport.get(&self.endpoint("stations/"))?;
+        let json: Res = parse(&data)?;
+
+        Ok(json.result.stations)
+    }
+    /**
+    Fetch a list of the song and artist in the history of a station
+    */
+    pub fn history(&self, id: usize) -> Result<Vec<Title>, ApiError> {
+        let data = self
+            .transport
+            .get(&self.endpoint(&format!("station/history/?id={}", id)))?;
+        let json: ResHistory = parse(&data)?;
+
+        Ok(json.result.history)
+    }
+
+    /**
+    Fetch the current playing song, None if the station did not play anything yet
+    */
+    pub fn now_playing(&self, id: usize) -> Result<Option<Title>, ApiError> {
+        match self.now_playing_back(id) {
+            Ok(title) => Ok(Some(title)),
+            Err(_) => Ok(self.history(id)?.into_iter().next()),
+        }
+    }
+    /**
+    Fetch the current playing song from the now endpoint
+    */
+    fn now_playing_back(&self, id: usize) -> Result<Title, ApiError> {
+        self.now_playing_all()?
+            .remove(&id)
+            .ok_or(ApiError::UnknownStation(id))
+    }
+    /**
+    Fetch the current playing song of every station, by station id
+    */
+    pub fn now_playing_all(&self) -> Result<HashMap<usize, Title>, ApiError> {
+        let data = self.transport.get(&self.endpoint("stations/now/"))?;
+        let json: ResNowPlaying = parse(&data)?;
+
+        Ok(json
+            .result
+            .into_iter()
+            .map(|station| (station.id, station.track))
+            .collect())
+    }
 }
 
-/**
-Read from url
-**/
-fn read(url: &str) -> Result<Vec<u8>, ApiError> {
-    let mut data = Vec::new();
-    let mut handle = Easy::new();
-    handle.url(url).unwrap();
-    let res;
-    {
-        let mut transfer = handle.transfer();
-        transfer
-            .write_function(|new_data| {
-                data.extend_from_slice(new_data);
-                Ok(new_data.len())
-            })
-            .unwrap();
-        res = transfer.perform();
-    }
-    match res {
-        Ok(_) => Ok(data),
-        Err(_) => Err(ApiError::NoConnection),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    const BASE_URL: &str = "https://api.test";
+
+    fn fixtures() -> ApiClient {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/api");
+        ApiClient::with_transport(BASE_URL, Arc::new(FixtureTransport::new(BASE_URL, dir)))
+    }
+
+    /**
+    Client on a directory of its own, serving the given files
+     */
+    fn serving(name: &str, files: &[(&str, &[u8])]) -> ApiClient {
+        let dir = env::temp_dir().join(format!("rrt-api-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), content).unwrap();
+        }
+        ApiClient::with_transport(BASE_URL, Arc::new(FixtureTransport::new(BASE_URL, dir)))
+    }
+
+    fn title(song: &str, artist: &str) -> Title {
+        Title {
+            song: song.to_string(),
+            artist: artist.to_string(),
+        }
+    }
+
+    #[test]
+    fn stations_list() {
+        let stations = fixtures().stations_list().unwrap();
+        let prefixes: Vec<&str> = stations.iter().map(|s| s.prefix.as_str()).collect();
+        assert_eq!(prefixes, ["rr_main", "trancehits", "hiphop"]);
+        assert_eq!(stations[0].id, 15016);
+        assert_eq!(stations[0].title, "Record");
+        assert_eq!(stations[1].genre[0].name, "Trance");
+    }
+
+    #[test]
+    fn file_url_serves_the_fixtures() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/api");
+        let api = ApiClient::new(&format!("file://{}", dir.display()));
+        assert_eq!(api.stations_list().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn now_playing() {
+        let api = fixtures();
+        assert_eq!(
+            api.now_playing(15016).unwrap(),
+            Some(title("Blah Blah Blah", "Armin van Buuren"))
+        );
+        // not in the now endpoint, read from the history of the station
+        assert_eq!(
+            api.now_playing(1).unwrap(),
+            Some(title("Adagio For Strings", "Tiesto"))
+        );
+    }
+
+    #[test]
+    fn now_playing_without_track() {
+        let api = serving(
+            "no-track",
+            &[
+                ("stations_now.json", b"{\"result\": []}"),
+                ("station_history.json", b"{\"result\": {\"history\": []}}"),
+            ],
+        );
+        assert_eq!(api.now_playing(15016).unwrap(), None);
+    }
+
+    #[test]
+    fn now_playing_all() {
+        let now = fixtures().now_playing_all().unwrap();
+        assert_eq!(now.len(), 3);
+        assert_eq!(now[&15017], title("9 PM (Till I Come)", "ATB"));
+        assert_eq!(now[&15018], title("Still D.R.E.", "Dr. Dre"));
+    }
+
+    #[test]
+    fn list_genres_sorted_without_duplicates() {
+        let mut stations = fixtures().stations_list().unwrap();
+        stations.push(stations[0].clone());
+        let names: Vec<String> = list_genres(&stations).into_iter().map(|g| g.name).collect();
+        assert_eq!(names, ["Hip-Hop", "House", "Trance"]);
+    }
+
+    #[test]
+    fn missing_fixture() {
+        let api = serving("missing", &[]);
+        assert!(matches!(
+            api.stations_list(),
+            Err(ApiError::HttpStatus(404))
+        ));
+    }
+
+    #[test]
+    fn malformed_json() {
+        let api = serving(
+            "malformed",
+            &[("stations.json", b"{\"result\": {\"stations\": [")],
+        );
+        assert!(matches!(api.stations_list(), Err(ApiError::Decode(_))));
+        let api = serving("not-utf8", &[("stations.json", b"\xff\xfe")]);
+        assert!(matches!(api.stations_list(), Err(ApiError::Decode(_))));
+    }
+
+    #[test]
+    fn schema_mismatch() {
+        let api = serving(
+            "schema",
+            &[("stations.json", b"{\"result\": {\"stations\": 3}}")],
+        );
+        assert!(matches!(api.stations_list(), Err(ApiError::Schema(_))));
+    }
+
+    #[test]
+    fn stream_url_fallbacks() {
+        let station = Station {
+            stream_128: "128".to_string(),
+            stream_320: "320".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(station.stream_url(Quality::High), "320");
+        assert_eq!(station.stream_url(Quality::Medium), "128");
+        assert_eq!(station.stream_url(Quality::Low), "128");
+        assert_eq!(station.stream_url(Quality::Hls), "320");
+        assert_eq!(station.stream_quality(Quality::Low), Quality::Medium);
+        assert_eq!(station.stream_quality(Quality::Hls), Quality::High);
+
+        let hls_only = Station {
+            stream_hls: "hls".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(hls_only.stream_url(Quality::Low), "hls");
+        assert_eq!(Station::default().stream_url(Quality::High), "");
     }
 }
@@ -1,41 +1,89 @@
-use crate::config::{read_favorite, toggle_to_favorite};
-use crate::mpris::{self, launch_mpris_server, Command, Response};
+use crate::alarm::{alarm_rang, due_alarm, parse_days, parse_time, Alarm};
+use crate::backend::Backend;
+use crate::config::{
+    read_alarms, read_favorite, read_stations_cache, toggle_to_favorite, write_alarms,
+    write_config, write_stations_cache, Config,
+};
+use crate::daemon::{self, Reply, Request};
+use crate::history::History;
+use crate::mpris::{
+    self, launch_mpris_server, player_volume, Command, MprisServer, Playlist, Snapshot,
+};
+use crate::notifier::Notifier;
+use crate::recorder::{Recorder, TRACK_POLL};
+use crate::timer::{FadeIn, SleepTimer};
 use crate::tools::{read_icons, StationsArtList};
-use crate::ui::{render_help, render_stations};
+use crate::ui::{
+    render_alarms, render_genres, render_help, render_history, render_overview, render_stations,
+};
 use crate::{
-    api::{now_playing, stations_list, Station},
-    player::Player,
+    api::{list_genres, ApiClient, ApiError, Genre, Quality, Station, Title},
+    player::{PlaybackState, Player, PlayerEvent},
 };
-use crossbeam::channel;
+use chrono::{DateTime, Local};
 use crossbeam::channel::Sender;
+use crossbeam::{channel, select};
 use crossterm::{
     event::{self, Event as CEvent, KeyCode, KeyEvent},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use rand::random;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::{
-    io,
+    io::{self, Stdout},
     process::exit,
     thread,
     time::{Duration, Instant},
 };
-use tui::{backend::CrosstermBackend, widgets::ListState, Terminal};
+use tui::{
+    backend::CrosstermBackend,
+    widgets::{ListState, TableState},
+    Terminal,
+};
 use tui_input::backend::crossterm::EventHandler;
 use tui_input::Input;
 
 pub enum Event {
     Input(KeyEvent),
     Tick,
-    NowPlaying,
+    Player(PlayerEvent),
     Mpris(mpris::Command),
+    Catalogue(Result<Vec<Station>, ApiError>),
+    Track(Result<Option<Title>, ApiError>),
+    StationHistory(usize, Result<Vec<Title>, ApiError>),
+    Overview(Result<HashMap<usize, Title>, ApiError>),
 }
 
 #[derive(Copy, Clone, Debug)]
 pub enum Context {
     Stations,
     Help,
+    Genres,
+    Alarms,
+    History,
+    Overview,
+}
+
+/**
+Column the now playing overview is sorted by
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OverviewSort {
+    Station,
+    Artist,
+    Song,
+}
+
+impl OverviewSort {
+    fn next(self) -> Self {
+        match self {
+            OverviewSort::Station => OverviewSort::Artist,
+            OverviewSort::Artist => OverviewSort::Song,
+            OverviewSort::Song => OverviewSort::Station,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -45,55 +93,112 @@ pub enum MenuItem {
 }
 
 pub const TICK_RATE: Duration = Duration::from_millis(200);
+/// Delay between two attempts to refresh a stale stations list
+const CATALOGUE_RETRY: Duration = Duration::from_secs(60);
+/// Delay between two refreshes of the now playing overview
+const OVERVIEW_REFRESH: Duration = Duration::from_secs(30);
+/// Shown when the api knows no track played by the station
+const NO_TRACK: &str = "No track played yet";
 
 pub struct Status {
     pub station: Station,
-    pub playing: bool,
-}
-
-impl Status {
-    pub fn mpris_playing(&self) -> String {
-        if self.playing {
-            "Playing".to_string()
-        } else {
-            "Stopped".to_string()
-        }
-    }
+    pub state: PlaybackState,
+    pub quality: Quality,
+    pub volume: u8,
+    pub muted: bool,
+    pub recording: bool,
+    pub sleep: Option<String>,
 }
 
 impl Display for Status {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let np = match self.playing {
-            true => "Now playing",
-            false => "Paused",
-        };
-        write!(f, "{} : {} ", np, self.station.title)
+        write!(
+            f,
+            "{} : {} [{}] ",
+            self.state, self.station.title, self.quality
+        )?;
+        if self.recording {
+            write!(f, "● REC ")?;
+        }
+        if let Some(sleep) = &self.sleep {
+            write!(f, "Sleep in {} ", sleep)?;
+        }
+        Ok(())
     }
 }
 
 pub struct App {
+    api: ApiClient,
+    config: Config,
     stations_list_std: Vec<Station>,
     stations_list_fav: Vec<Station>,
+    pub catalogue_stale: bool,
+    catalogue_refreshed: Option<Instant>,
     player: Player,
+    /// Audio output chosen, the stream quality asked for depends on it
+    backend: Backend,
+    /// Volume applied by the backend
+    volume: u8,
     pub icon_list: StationsArtList,
     active_context: Context,
     pub filtering: bool,
     pub music_title: String,
+    /// Track heard on the playing station, from the stream metadata or the api
+    track: Option<Title>,
     pub stations_list_state: ListState,
     pub playing_station: Station,
     pub active_menu_item: MenuItem,
     pub filter: Input,
     last_selected: Option<usize>,
+    pub genre_filter: Option<Genre>,
+    pub genre_list_state: ListState,
+    recorder: Recorder,
+    track_polled: Option<Instant>,
+    sleep_timer: Option<SleepTimer>,
+    pub alarms: Vec<Alarm>,
+    pub alarm_list_state: ListState,
+    /// New alarm being typed, as `HH:MM [days]`
+    pub alarm_input: Option<Input>,
+    pub alarm_error: Option<String>,
+    alarms_checked: DateTime<Local>,
+    alarm_fade: Option<FadeIn>,
+    pub history: History,
+    notifier: Option<Notifier>,
+    mpris: Option<MprisServer>,
+    /// The player is the running daemon, which serves MPRIS and the notifications
+    attached: bool,
+    pub history_list_state: ListState,
+    /// Last tracks of the selected station, None while they are fetched
+    pub station_history: Option<Result<Vec<Title>, ApiError>>,
+    station_history_id: Option<usize>,
+    /// Current track of every station, by station id
+    pub now_playing: HashMap<usize, Title>,
+    now_playing_refreshed: Option<Instant>,
+    pub overview_error: Option<ApiError>,
+    pub overview_sort: OverviewSort,
+    pub overview_filter: Input,
+    pub overview_filtering: bool,
+    pub overview_state: TableState,
 }
 
 impl App {
-    pub fn new() -> Self {
-        //try to get the stations list. Exit the program if impossible
-        let stations_list_std = match stations_list() {
-            Ok(list) => list,
-            Err(_) => {
-                eprintln!("No connection available !");
-                exit(1)
+    pub fn new(api: ApiClient, config: Config, backend: Backend) -> Self {
+        //use the cached stations list, it will be refreshed in the background.
+        //Without cache, try to get the stations list. Exit the program if impossible
+        let cached = read_stations_cache().unwrap_or_default();
+        let catalogue_stale = !cached.is_empty();
+        let stations_list_std = if catalogue_stale {
+            cached
+        } else {
+            match api.stations_list() {
+                Ok(list) => {
+                    let _ = write_stations_cache(&list);
+                    list
+                }
+                Err(error) => {
+                    eprintln!("Could not fetch the stations : {}", error);
+                    exit(1)
+                }
             }
         };
 
@@ -113,27 +218,75 @@ impl App {
         //initiate the active list
         let mut stations_list_state = ListState::default();
         stations_list_state.select(Some(0));
-        let playing_station = match active_menu_item {
+        let mut playing_station = match active_menu_item {
             MenuItem::Favorite(_) => &stations_list_fav,
             MenuItem::Standard(_) => &stations_list_std,
         }[0]
         .clone();
+        // show the station the daemon plays
+        let attached = backend == Backend::Daemon;
+        if attached {
+            if let Ok(Reply::Status { station, .. }) = daemon::request(&Request::Status) {
+                playing_station = *station;
+            }
+        }
 
         let input = Input::default();
+        let icon_list = read_icons(&stations_list_std).expect("could not retrieve icons");
+        let player = Player::new(
+            playing_station
+                .stream_url(backend.playable(config.quality))
+                .to_string(),
+            &config,
+            backend,
+        );
+        let recorder = Recorder::new(&config);
 
         App {
+            api,
+            config,
             stations_list_std,
             stations_list_fav,
-            player: Player::new(playing_station.stream_320.to_string()),
-            icon_list: read_icons().expect("could not retrieve icons"),
+            catalogue_stale,
+            catalogue_refreshed: None,
+            volume: player.volume(),
+            player,
+            backend,
+            icon_list,
             active_context: Context::Stations,
             music_title: String::from("Press n to show current song"),
+            track: None,
             stations_list_state,
             playing_station,
             active_menu_item,
             filtering: false,
             filter: input,
             last_selected: None,
+            genre_filter: None,
+            genre_list_state: ListState::default(),
+            recorder,
+            track_polled: None,
+            sleep_timer: None,
+            alarms: read_alarms().unwrap_or_default(),
+            alarm_list_state: ListState::default(),
+            alarm_input: None,
+            alarm_error: None,
+            alarms_checked: Local::now(),
+            alarm_fade: None,
+            history: History::new(),
+            notifier: None,
+            mpris: None,
+            attached,
+            history_list_state: ListState::default(),
+            station_history: None,
+            station_history_id: None,
+            now_playing: HashMap::new(),
+            now_playing_refreshed: None,
+            overview_error: None,
+            overview_sort: OverviewSort::Station,
+            overview_filter: Input::default(),
+            overview_filtering: false,
+            overview_state: TableState::default(),
         }
     }
 
@@ -144,16 +297,120 @@ impl App {
         }
     }
 
-    fn get_filtered_station<'a>(&'a self, stations: &'a [Station]) -> Vec<&Station> {
+    fn get_filtered_station<'a>(&'a self, stations: &'a [Station]) -> Vec<&'a Station> {
         let value = self.filter.value().to_lowercase();
         stations
             .iter()
             .filter(|s| {
                 s.title.to_lowercase().contains(&value) || s.tooltip.to_lowercase().contains(&value)
             })
+            .filter(|s| self.in_genre(s))
             .collect()
     }
 
+    /**
+    Station belongs to the selected genre. Favorites saved without genre use the ones of the stations list
+     */
+    fn in_genre(&self, station: &Station) -> bool {
+        match &self.genre_filter {
+            Some(genre) => self
+                .stations_list_std
+                .iter()
+                .find(|s| *s == station)
+                .unwrap_or(station)
+                .genre
+                .contains(genre),
+            None => true,
+        }
+    }
+
+    /**
+    All the genres of the stations list sorted by name
+     */
+    pub fn get_genres(&self) -> Vec<Genre> {
+        list_genres(&self.stations_list_std)
+    }
+
+    /**
+    Number of stations in the list, whatever the genre and the search
+     */
+    pub fn count_stations(&self) -> usize {
+        self.stations_list_std.len()
+    }
+
+    /**
+    Number of stations in a genre
+     */
+    pub fn count_genre(&self, genre: &Genre) -> usize {
+        self.stations_list_std
+            .iter()
+            .filter(|s| s.genre.contains(genre))
+            .count()
+    }
+
+    /**
+    Select the genre highlighted in the genre browser, the first entry removing the filter
+     */
+    fn select_genre(&mut self) {
+        if let Some(selected) = self.genre_list_state.selected() {
+            self.genre_filter = match selected {
+                0 => None,
+                n => self.get_genres().get(n - 1).cloned(),
+            };
+        }
+        let first = match self.get_stations_list().is_empty() {
+            true => None,
+            false => Some(0),
+        };
+        self.stations_list_state.select(first);
+        self.active_context = Context::Stations;
+    }
+
+    /**
+    Playlist matching the list shown, if any
+     */
+    fn active_playlist(&self) -> Option<Playlist> {
+        match (self.active_menu_item, &self.genre_filter) {
+            (MenuItem::Favorite(_), None) => Some(Playlist::Favorites),
+            (MenuItem::Standard(_), Some(genre)) => Some(Playlist::Genre(genre.clone())),
+            _ => None,
+        }
+    }
+
+    /**
+    Show the stations of a playlist chosen by a MPRIS client and play the first one. Next and Previous then step through them
+     */
+    fn activate_playlist(&mut self, playlist: Playlist) {
+        match playlist {
+            Playlist::Favorites => {
+                self.genre_filter = None;
+                self.active_menu_item = MenuItem::Favorite(true);
+            }
+            Playlist::Genre(genre) => {
+                self.genre_filter = Some(genre);
+                self.active_menu_item = MenuItem::Standard(true);
+            }
+        }
+        self.filter.reset();
+        self.stations_list_state.select(Some(0));
+        if let Some(station) = self.get_selected_station() {
+            self.play_station(station);
+        }
+    }
+
+    /**
+    Move in the genre browser. The first entry is the whole list
+     */
+    fn move_genre(&mut self, forward: bool) {
+        let amount = self.get_genres().len() + 1;
+        let selected = self.genre_list_state.selected().unwrap_or(0);
+        let next = match forward {
+            true => (selected + 1) % amount,
+            false => (selected + amount - 1) % amount,
+        };
+        self.genre_list_state.select(Some(next));
+    }
+
     pub fn get_stations_list_std(&self) -> Vec<&Station> {
         self.get_filtered_station(&self.stations_list_std)
     }
@@ -162,10 +419,22 @@ impl App {
         self.get_filtered_station(&self.stations_list_fav)
     }
 
+    /**
+    Stream quality preferred, among the ones the backend can play
+     */
+    fn quality(&self) -> Quality {
+        self.backend.playable(self.config.quality)
+    }
+
     pub fn get_status(&self) -> Status {
         Status {
             station: self.playing_station.clone(),
-            playing: self.player.is_playing(),
+            state: self.player.state(),
+            quality: self.playing_station.stream_quality(self.quality()),
+            volume: self.volume,
+            muted: self.player.is_muted(),
+            recording: self.recorder.is_recording(),
+            sleep: self.sleep_timer.as_ref().map(|timer| timer.to_string()),
         }
     }
 
@@ -180,6 +449,9 @@ impl App {
     fn next(&mut self) {
         if let Some(selected) = self.stations_list_state.selected() {
             let amount_stations = self.get_stations_list().len();
+            if amount_stations == 0 {
+                return;
+            }
 
             if selected >= amount_stations - 1 {
                 // wrap to start
@@ -193,6 +465,9 @@ impl App {
     fn previous(&mut self) {
         if let Some(selected) = self.stations_list_state.selected() {
             let amount_stations = self.get_stations_list().len();
+            if amount_stations == 0 {
+                return;
+            }
 
             if selected > 0 {
                 self.stations_list_state.select(Some(selected - 1));
@@ -208,18 +483,509 @@ impl App {
             MenuItem::Standard(b) => MenuItem::Standard(!b),
         };
     }
-    fn update_now_playing(&mut self) {
-        #[cfg(feature = "libmpv_player")]
-        {
-            if self.get_status().playing {
-                if let Some(title) = self.player.now_playing() {
-                    self.music_title = title;
+    /**
+    Fetch the stations list in the background if the current one comes from the cache
+     */
+    fn refresh_catalogue(&mut self, tx: &Sender<Event>) {
+        if !self.catalogue_stale {
+            return;
+        }
+        if let Some(last) = self.catalogue_refreshed {
+            if last.elapsed() < CATALOGUE_RETRY {
+                return;
+            }
+        }
+        self.catalogue_refreshed = Some(Instant::now());
+
+        let api = self.api.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(Event::Catalogue(api.stations_list()));
+        });
+    }
+
+    /**
+    Replace the stations list with a freshly fetched one
+     */
+    fn update_catalogue(&mut self, list: Vec<Station>) {
+        let _ = write_stations_cache(&list);
+        self.stations_list_std = list;
+        self.catalogue_stale = false;
+
+        if let MenuItem::Standard(_) = self.active_menu_item {
+            let amount_stations = self.get_stations_list().len();
+            match self.stations_list_state.selected() {
+                Some(selected) if selected < amount_stations => {}
+                _ => self.stations_list_state.select(Some(0)),
+            }
+        }
+    }
+
+    /**
+    Switch to the next stream quality, save it and restart the playing station with it
+     */
+    fn toggle_quality(&mut self) {
+        self.config.quality = self.backend.playable(self.quality().next());
+        let _ = write_config(&self.config);
+
+        if self.player.is_playing() {
+            self.play_station(self.playing_station.clone());
+        }
+    }
+
+    /**
+    Play a station with the chosen quality, the recording follows the switch
+     */
+    fn play_station(&mut self, station: Station) -> bool {
+        let url = station.stream_url(self.quality()).to_string();
+        if !self.player.force_play(&url) {
+            return false;
+        }
+        if station != self.playing_station {
+            self.history.finish();
+            self.track = None;
+            if let Some(notifier) = &self.notifier {
+                notifier.notify(&station.title, "", &station);
+            }
+        }
+        if let Err(error) = self.recorder.station_changed(&self.player, &station, &url) {
+            self.music_title = format!("Recording failed : {}", error);
+        }
+        self.playing_station = station;
+        true
+    }
+
+    /**
+    Stop the playback, the track heard is over
+     */
+    fn stop(&mut self) {
+        self.player.stop();
+        self.history.finish();
+    }
+
+    /**
+    Pause the playback, the track heard is over
+     */
+    fn pause(&mut self) {
+        self.player.pause();
+        self.history.finish();
+    }
+
+    /**
+    Pause or resume the playback. The track heard is over when pausing, the next title starts a new one
+     */
+    fn toggle_play(&mut self) {
+        if self.player.is_playing() {
+            self.history.finish();
+        }
+        self.player.toggle_play();
+    }
+
+    /**
+    Start recording the playing station or stop the current recording
+     */
+    fn toggle_recording(&mut self) {
+        if self.recorder.is_recording() {
+            self.music_title = match self.recorder.stop(&self.player) {
+                Ok(()) => "Recording stopped".to_string(),
+                Err(error) => format!("Recording failed : {}", error),
+            };
+            return;
+        }
+
+        // the title known so far, the track polled in the background completes it
+        let station = self.playing_station.clone();
+        let url = station.stream_url(self.quality());
+        let title = self
+            .player
+            .now_playing()
+            .and_then(|title| Title::from_stream_title(&title))
+            .or_else(|| self.now_playing.get(&station.id).cloned());
+        self.music_title = match self.recorder.start(&self.player, &station, url, title) {
+            Ok(path) => format!("Recording to {}", path.display()),
+            Err(error) => format!("Recording failed : {}", error),
+        };
+        self.track_polled = None;
+    }
+
+    /**
+    Check the playing track in the background while recording, to split the files
+     */
+    fn poll_track(&mut self, tx: &Sender<Event>) {
+        if !self.recorder.is_recording() {
+            return;
+        }
+        if let Some(last) = self.track_polled {
+            if last.elapsed() < TRACK_POLL {
+                return;
+            }
+        }
+        self.track_polled = Some(Instant::now());
+
+        let api = self.api.clone();
+        let id = self.playing_station.id;
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(Event::Track(api.now_playing(id)));
+        });
+    }
+
+    fn track_changed(&mut self, title: Title) {
+        let url = self.playing_station.stream_url(self.quality());
+        match self.recorder.track_changed(&self.player, url, title) {
+            Ok(Some(path)) => self.music_title = format!("Recording to {}", path.display()),
+            Ok(None) => {}
+            Err(error) => self.music_title = format!("Recording failed : {}", error),
+        }
+    }
+
+    /**
+    Change the volume and remember it for the next launch
+     */
+    fn set_volume(&mut self, volume: u8) {
+        self.player.set_volume(volume);
+        self.config.volume = self.player.volume();
+        let _ = write_config(&self.config);
+        if let Some(timer) = &mut self.sleep_timer {
+            timer.set_volume(self.config.volume);
+        }
+        self.alarm_fade = None;
+    }
+
+    /**
+    Cycle through the sleep timer presets, the volume is restored when the timer is removed
+     */
+    fn toggle_sleep_timer(&mut self) {
+        let next = SleepTimer::next_preset(self.sleep_timer.as_ref());
+        let volume = match self.sleep_timer.take() {
+            Some(timer) => timer.initial_volume(),
+            None => self.player.volume(),
+        };
+        self.player.set_volume(volume);
+        self.sleep_timer = next.map(|duration| SleepTimer::new(duration, volume));
+    }
+
+    /**
+    Fade the volume out at the end of the sleep timer and stop the player when it is over
+     */
+    fn update_sleep_timer(&mut self) {
+        let timer = match &self.sleep_timer {
+            Some(timer) => timer,
+            None => return,
+        };
+        if timer.is_over() {
+            let volume = timer.initial_volume();
+            self.stop();
+            self.player.set_volume(volume);
+            self.sleep_timer = None;
+        } else if timer.volume() != self.player.volume() {
+            self.player.set_volume(timer.volume());
+        }
+    }
+
+    fn open_alarms(&mut self) {
+        self.alarms = read_alarms().unwrap_or_default();
+        self.alarm_list_state.select(if self.alarms.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+        self.alarm_input = None;
+        self.alarm_error = None;
+        self.active_context = Context::Alarms;
+    }
+
+    fn move_alarm(&mut self, forward: bool) {
+        let amount = self.alarms.len();
+        if amount == 0 {
+            return;
+        }
+        let selected = self.alarm_list_state.selected().unwrap_or(0);
+        self.alarm_list_state.select(Some(if forward {
+            (selected + 1) % amount
+        } else {
+            (selected + amount - 1) % amount
+        }));
+    }
+
+    /**
+    Add an alarm for the selected station from the typed text
+     */
+    fn add_alarm(&mut self) {
+        let text = match self.alarm_input.take() {
+            Some(input) => input.value().to_string(),
+            None => return,
+        };
+        let mut parts = text.split_whitespace();
+        let time = parts.next().unwrap_or_default();
+        let parsed = parse_time(time).and_then(|time| {
+            let days = match parts.next() {
+                Some(days) => parse_days(days)?,
+                None => Vec::new(),
+            };
+            Ok((time, days))
+        });
+        let (time, days) = match parsed {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                self.alarm_error = Some(error);
+                return;
+            }
+        };
+
+        let station = self
+            .get_selected_station()
+            .unwrap_or_else(|| self.playing_station.clone());
+        let alarm = Alarm::new(&self.alarms, station.prefix, time, days);
+        self.alarms.push(alarm);
+        self.alarm_list_state.select(Some(self.alarms.len() - 1));
+        self.alarm_error = write_alarms(&self.alarms).err().map(|e| e.to_string());
+    }
+
+    fn remove_alarm(&mut self) {
+        if let Some(selected) = self.alarm_list_state.selected() {
+            if selected < self.alarms.len() {
+                self.alarms.remove(selected);
+                self.alarm_error = write_alarms(&self.alarms).err().map(|e| e.to_string());
+                if self.alarms.is_empty() {
+                    self.alarm_list_state.select(None);
+                } else if selected == self.alarms.len() {
+                    self.alarm_list_state.select(Some(selected - 1));
                 }
             }
         }
-        #[cfg(feature = "rodio_player")]
-        {
-            self.music_title = now_playing(self.playing_station.id).unwrap().to_string();
+    }
+
+    /**
+    Play the station of an alarm that just rang and raise the volume progressively
+     */
+    fn check_alarms(&mut self) {
+        let now = Local::now();
+        let due = due_alarm(&self.alarms, self.alarms_checked, now).cloned();
+        self.alarms_checked = now;
+
+        if let Some(alarm) = due {
+            alarm_rang(&alarm);
+            self.alarms = read_alarms().unwrap_or_default();
+            let station = self
+                .stations_list_std
+                .iter()
+                .find(|s| s.prefix == alarm.station)
+                .cloned();
+            if let Some(station) = station {
+                self.player.set_volume(0);
+                self.play_station(station);
+                self.alarm_fade = Some(FadeIn::new(self.config.volume));
+            }
+        }
+
+        if let Some(fade) = &self.alarm_fade {
+            if fade.is_over() {
+                self.player.set_volume(self.config.volume);
+                self.alarm_fade = None;
+            } else {
+                self.player.set_volume(fade.volume());
+            }
+        }
+    }
+
+    /**
+    Title of a station from its id, used for the listening history
+     */
+    pub fn station_title(&self, id: usize) -> String {
+        self.stations_list_std
+            .iter()
+            .find(|station| station.id == id)
+            .map_or_else(|| format!("#{}", id), |station| station.title.clone())
+    }
+
+    fn move_history(&mut self, forward: bool) {
+        let amount = self.history.recent().len();
+        if amount == 0 {
+            return;
+        }
+        let selected = self.history_list_state.selected().unwrap_or(0);
+        self.history_list_state.select(Some(if forward {
+            (selected + 1).min(amount - 1)
+        } else {
+            selected.saturating_sub(1)
+        }));
+    }
+
+    /**
+    Fetch the last tracks of the selected station in the background when the selection changed
+     */
+    fn refresh_station_history(&mut self, tx: &Sender<Event>) {
+        let id = match self.get_selected_station() {
+            Some(station) => station.id,
+            None => return,
+        };
+        if self.station_history_id == Some(id) {
+            return;
+        }
+        self.station_history_id = Some(id);
+        self.station_history = None;
+
+        let api = self.api.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(Event::StationHistory(id, api.history(id)));
+        });
+    }
+
+    /**
+    Stations with their current track, filtered and sorted as chosen in the overview
+     */
+    pub fn get_overview(&self) -> Vec<(&Station, Option<&Title>)> {
+        let filter = self.overview_filter.value().to_lowercase();
+        let mut overview: Vec<(&Station, Option<&Title>)> = self
+            .stations_list_std
+            .iter()
+            .map(|station| (station, self.now_playing.get(&station.id)))
+            .filter(|(station, title)| {
+                filter.is_empty()
+                    || station.title.to_lowercase().contains(&filter)
+                    || title.is_some_and(|title| {
+                        title.artist.to_lowercase().contains(&filter)
+                            || title.song.to_lowercase().contains(&filter)
+                    })
+            })
+            .collect();
+
+        match self.overview_sort {
+            OverviewSort::Station => {}
+            OverviewSort::Artist => {
+                overview.sort_by_key(|(_, title)| title.map(|title| title.artist.to_lowercase()))
+            }
+            OverviewSort::Song => {
+                overview.sort_by_key(|(_, title)| title.map(|title| title.song.to_lowercase()))
+            }
+        }
+        overview
+    }
+
+    fn open_overview(&mut self, tx: &Sender<Event>) {
+        self.overview_state.select(Some(0));
+        self.now_playing_refreshed = None;
+        self.active_context = Context::Overview;
+        self.refresh_overview(tx);
+    }
+
+    /**
+    Fetch the current track of every station in the background while the overview is shown
+     */
+    fn refresh_overview(&mut self, tx: &Sender<Event>) {
+        if let Some(last) = self.now_playing_refreshed {
+            if last.elapsed() < OVERVIEW_REFRESH {
+                return;
+            }
+        }
+        self.now_playing_refreshed = Some(Instant::now());
+
+        let api = self.api.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(Event::Overview(api.now_playing_all()));
+        });
+    }
+
+    fn move_overview(&mut self, forward: bool) {
+        let amount = self.get_overview().len();
+        if amount == 0 {
+            return;
+        }
+        let selected = self.overview_state.selected().unwrap_or(0);
+        self.overview_state.select(Some(if forward {
+            (selected + 1) % amount
+        } else {
+            (selected + amount - 1) % amount
+        }));
+    }
+
+    /**
+    Keep the selection in the overview table after it was filtered
+     */
+    fn clamp_overview(&mut self) {
+        let amount = self.get_overview().len();
+        match self.overview_state.selected() {
+            Some(selected) if selected < amount => {}
+            _ => self.overview_state.select(Some(0)),
+        }
+    }
+
+    /**
+    New title from the stream metadata
+     */
+    fn title_changed(&mut self, title: String) {
+        self.track = Title::from_stream_title(&title);
+        if let Some(parsed) = &self.track {
+            if let Some(notifier) = &self.notifier {
+                if self.music_title != title {
+                    notifier.notify(&title, &self.playing_station.title, &self.playing_station);
+                }
+            }
+            self.history.heard(self.playing_station.id, parsed.clone());
+        }
+        self.music_title = title;
+    }
+
+    /**
+    Publish the state of the player to the MPRIS clients
+     */
+    fn publish_mpris(&self) {
+        if let Some(mpris) = &self.mpris {
+            mpris.publish(self.mpris_snapshot());
+        }
+    }
+
+    fn mpris_snapshot(&self) -> Snapshot {
+        let state = self.player.state();
+        Snapshot {
+            title: state.mpris_title(&self.music_title),
+            track: state.mpris_track(self.track.as_ref()),
+            station: Some(self.playing_station.clone()),
+            status: state.mpris_status(),
+            volume: self.player.volume() as f64 / 100.0,
+            sleep: self.sleep_timer.as_ref().map(SleepTimer::end),
+            playlists: Playlist::list(&self.stations_list_fav, self.get_genres()),
+            active_playlist: self.active_playlist(),
+        }
+    }
+
+    /**
+    Restore the terminal before leaving
+     */
+    fn quit(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+        if self.recorder.is_recording() {
+            let _ = self.recorder.stop(&self.player);
+        }
+        self.recorder.finish();
+        self.history.finish();
+        let mut stdout = io::stdout();
+        stdout.execute(LeaveAlternateScreen)?;
+        disable_raw_mode()?;
+        terminal.show_cursor()
+    }
+
+    fn update_now_playing(&mut self) {
+        if !self.player.is_playing() {
+            return;
+        }
+        // without stream metadata, ask the api
+        match self.player.now_playing() {
+            Some(title) => self.title_changed(title),
+            None => {
+                let track = self.api.now_playing(self.playing_station.id);
+                self.music_title = match &track {
+                    Ok(Some(title)) => {
+                        self.history.heard(self.playing_station.id, title.clone());
+                        title.to_string()
+                    }
+                    Ok(None) => NO_TRACK.to_string(),
+                    Err(error) => error.to_string(),
+                };
+                self.track = track.ok().flatten();
+            }
         }
     }
 
@@ -237,20 +1003,36 @@ impl App {
         //setup event emitter and receiver
         let (tx, rx) = channel::bounded(1);
         event_sender(tx.clone());
+        // the MPRIS commands never wait for the interface to be drawn
+        let (mpris_tx, mpris_events) = channel::unbounded();
 
-        let (player_tx, player_rx) = channel::bounded(1);
-
-        let _conn = launch_mpris_server(tx, player_rx).await?;
+        if !self.attached {
+            self.mpris = Some(launch_mpris_server(mpris_tx.clone()).await?);
+            self.notifier = Notifier::new(&self.config).await;
+        }
+        let player_events = self.player.events();
 
         loop {
+            // the MPRIS clients read the state left by the last event
+            self.publish_mpris();
+
             //draw the corresponding context each tick
             terminal.draw(|rect| match self.active_context {
                 Context::Help => render_help(rect, self),
                 Context::Stations => render_stations(rect, self),
+                Context::Genres => render_genres(rect, self),
+                Context::Alarms => render_alarms(rect, self),
+                Context::History => render_history(rect, self),
+                Context::Overview => render_overview(rect, self),
             })?;
 
-            //wait for a tick or keyPress before continuing
-            match rx.recv()? {
+            //wait for a tick, a keyPress or a change of the player before continuing
+            let event = select! {
+                recv(rx) -> event => event?,
+                recv(mpris_events) -> event => event?,
+                recv(player_events) -> event => Event::Player(event?),
+            };
+            match event {
                 Event::Input(event) => {
                     if self.filtering {
                         match event.code {
@@ -274,17 +1056,115 @@ impl App {
                         self.filtering = !self.filtering;
                         continue;
                     }
+                    if let Context::Genres = self.active_context {
+                        match event.code {
+                            KeyCode::Down => self.move_genre(true),
+                            KeyCode::Up => self.move_genre(false),
+                            KeyCode::Enter => self.select_genre(),
+                            KeyCode::Esc | KeyCode::Char('g') => {
+                                self.active_context = Context::Stations
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if let Context::Overview = self.active_context {
+                        if self.overview_filtering {
+                            match event.code {
+                                KeyCode::Esc => {
+                                    self.overview_filter.reset();
+                                    self.overview_filtering = false;
+                                }
+                                KeyCode::Enter => self.overview_filtering = false,
+                                _ => {
+                                    self.overview_filter.handle_event(&CEvent::Key(event));
+                                }
+                            }
+                            self.clamp_overview();
+                            continue;
+                        }
+                        match event.code {
+                            KeyCode::Down => self.move_overview(true),
+                            KeyCode::Up => self.move_overview(false),
+                            KeyCode::Char('s') => {
+                                self.overview_sort = self.overview_sort.next();
+                                self.overview_state.select(Some(0));
+                            }
+                            KeyCode::Char('/') => self.overview_filtering = true,
+                            KeyCode::Enter => {
+                                let station = self.overview_state.selected().and_then(|selected| {
+                                    self.get_overview()
+                                        .get(selected)
+                                        .map(|(station, _)| (*station).clone())
+                                });
+                                if let Some(station) = station {
+                                    self.play_station(station);
+                                }
+                            }
+                            KeyCode::Esc | KeyCode::Char('o') => {
+                                self.active_context = Context::Stations
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if let Context::History = self.active_context {
+                        match event.code {
+                            KeyCode::Down => self.move_history(true),
+                            KeyCode::Up => self.move_history(false),
+                            KeyCode::Esc | KeyCode::Char('l') => {
+                                self.active_context = Context::Stations
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if let Context::Alarms = self.active_context {
+                        if let Some(input) = &mut self.alarm_input {
+                            match event.code {
+                                KeyCode::Enter => self.add_alarm(),
+                                KeyCode::Esc => self.alarm_input = None,
+                                _ => {
+                                    input.handle_event(&CEvent::Key(event));
+                                }
+                            }
+                            continue;
+                        }
+                        match event.code {
+                            KeyCode::Down => self.move_alarm(true),
+                            KeyCode::Up => self.move_alarm(false),
+                            KeyCode::Char('n') => {
+                                self.alarm_error = None;
+                                self.alarm_input = Some(Input::default())
+                            }
+                            KeyCode::Char('d') | KeyCode::Delete => self.remove_alarm(),
+                            KeyCode::Esc | KeyCode::Char('a') => {
+                                self.active_context = Context::Stations
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
                     match event.code {
                         KeyCode::Char('q') => {
-                            let mut stdout = io::stdout();
-                            stdout.execute(LeaveAlternateScreen)?;
-                            disable_raw_mode()?;
-                            terminal.show_cursor()?;
+                            self.quit(&mut terminal)?;
                             break;
                         }
                         KeyCode::Char('h') | KeyCode::Char('?') => {
                             self.active_context = Context::Help
                         }
+                        KeyCode::Char('g') => {
+                            let selected = match &self.genre_filter {
+                                Some(genre) => self
+                                    .get_genres()
+                                    .iter()
+                                    .position(|g| g == genre)
+                                    .map_or(0, |pos| pos + 1),
+                                None => 0,
+                            };
+                            self.genre_list_state.select(Some(selected));
+                            self.active_context = Context::Genres
+                        }
                         KeyCode::Char('f') => {
                             if let Some(selected_station) = self.get_selected_station() {
                                 self.stations_list_fav =
@@ -304,32 +1184,52 @@ impl App {
                         KeyCode::Char('N') => {
                             if let Some(selected_station) = self.get_selected_station() {
                                 let id = selected_station.id;
-                                self.music_title = now_playing(id).unwrap().to_string();
+                                let track = self.api.now_playing(id);
+                                self.music_title = match &track {
+                                    Ok(Some(title)) => title.to_string(),
+                                    Ok(None) => NO_TRACK.to_string(),
+                                    Err(error) => error.to_string(),
+                                };
+                                // the track of another station is not the one heard
+                                self.track = track
+                                    .ok()
+                                    .flatten()
+                                    .filter(|_| id == self.playing_station.id);
                             }
                         }
                         KeyCode::Char('r') => {
                             let random = random::<usize>() % self.get_stations_list().len();
 
-                            let temp = self.get_stations_list()[random].clone();
-                            let url = &temp.stream_320;
-
-                            if self.player.force_play(url) {
-                                self.playing_station = self.get_stations_list()[random].clone();
+                            let station = self.get_stations_list()[random].clone();
+                            if self.play_station(station) {
                                 self.stations_list_state.select(Some(random));
                             }
                         }
-                        KeyCode::Char(' ') => self.player.toggle_play(),
+                        KeyCode::Char(' ') => self.toggle_play(),
+                        KeyCode::Char('b') => self.toggle_quality(),
+                        KeyCode::Char('+') | KeyCode::Char('=') => {
+                            self.set_volume(self.player.volume().saturating_add(5))
+                        }
+                        KeyCode::Char('-') => {
+                            self.set_volume(self.player.volume().saturating_sub(5))
+                        }
+                        KeyCode::Char('m') => self.player.toggle_mute(),
+                        KeyCode::Char('R') => self.toggle_recording(),
+                        KeyCode::Char('s') => self.toggle_sleep_timer(),
+                        KeyCode::Char('a') => self.open_alarms(),
+                        KeyCode::Char('l') => {
+                            self.history_list_state.select(Some(0));
+                            self.active_context = Context::History
+                        }
+                        KeyCode::Char('o') => self.open_overview(&tx),
                         KeyCode::Enter => {
                             if let Some(selected_station) = self.get_selected_station() {
                                 let same = self.playing_station == selected_station;
 
                                 if !same {
-                                    let url = &selected_station.stream_320;
-                                    if self.player.force_play(url) {
-                                        self.playing_station = selected_station.clone();
-                                    }
+                                    self.play_station(selected_station);
                                 } else {
-                                    self.player.toggle_play()
+                                    self.toggle_play()
                                 }
                             }
                         }
@@ -356,7 +1256,11 @@ impl App {
                             }
                         },
                         KeyCode::Esc => match self.active_context {
-                            Context::Help => self.active_context = Context::Stations,
+                            Context::Help
+                            | Context::Genres
+                            | Context::Alarms
+                            | Context::History
+                            | Context::Overview => self.active_context = Context::Stations,
                             Context::Stations => {
                                 self.toggle_context();
                             }
@@ -373,39 +1277,66 @@ impl App {
                     }
                 }
 
-                Event::Tick => {}
-                Event::NowPlaying => self.update_now_playing(),
-                Event::Mpris(event) => {
-                    match event {
-                        Command::PlayPause => self.player.toggle_play(),
-                        Command::Stop => self.player.stop(),
-                        Command::Play => self.player.resume(),
-                        Command::Next => {
-                            self.next();
-                            let station = self.get_selected_station().unwrap();
-                            if self.player.force_play(&station.stream_320) {
-                                self.playing_station = station;
-                            }
-                        }
-                        Command::Previous => {
-                            self.previous();
-                            let station = self.get_selected_station().unwrap();
-                            if self.player.force_play(&station.stream_320) {
-                                self.playing_station = station;
-                            }
-                        }
-                        Command::NowPlaying => {
-                            player_tx
-                                .send(Response::NowPlaying{title:self.music_title.to_string(),artist:self.playing_station.title.clone()})
-                                .unwrap();
-                        }
-                        Command::Status => {
-                            player_tx
-                                .send(Response::Status(self.get_status().mpris_playing()))
-                                .unwrap();
-                        }
+                Event::Tick => {
+                    self.refresh_catalogue(&tx);
+                    self.poll_track(&tx);
+                    self.update_sleep_timer();
+                    self.check_alarms();
+                    self.refresh_station_history(&tx);
+                    if let Context::Overview = self.active_context {
+                        self.refresh_overview(&tx);
+                    }
+                }
+                Event::Player(PlayerEvent::Title(title)) => {
+                    if self.player.is_playing() {
+                        self.title_changed(title)
                     }
                 }
+                Event::Player(PlayerEvent::Volume(volume)) => self.volume = volume,
+                Event::Player(PlayerEvent::State(_)) => {}
+                Event::Catalogue(Ok(list)) => self.update_catalogue(list),
+                Event::Catalogue(Err(_)) => {}
+                Event::Track(Ok(Some(title))) => self.track_changed(title),
+                Event::Track(_) => {}
+                Event::Overview(Ok(now_playing)) => {
+                    self.now_playing = now_playing;
+                    self.overview_error = None;
+                }
+                Event::Overview(Err(error)) => self.overview_error = Some(error),
+                Event::StationHistory(id, history) => {
+                    if self.station_history_id == Some(id) {
+                        self.station_history = Some(history);
+                    }
+                }
+                Event::Mpris(event) => match event {
+                    Command::PlayPause => self.toggle_play(),
+                    Command::Pause => self.pause(),
+                    Command::Stop => self.stop(),
+                    Command::Play => self.player.resume(),
+                    Command::Next => {
+                        self.next();
+                        let station = self.get_selected_station().unwrap();
+                        self.play_station(station);
+                    }
+                    Command::Previous => {
+                        self.previous();
+                        let station = self.get_selected_station().unwrap();
+                        self.play_station(station);
+                    }
+                    Command::SetVolume(volume) => self.set_volume(player_volume(volume)),
+                    Command::ActivatePlaylist(path) => {
+                        let playlist = Playlist::list(&self.stations_list_fav, self.get_genres())
+                            .into_iter()
+                            .find(|playlist| playlist.path() == path);
+                        if let Some(playlist) = playlist {
+                            self.activate_playlist(playlist);
+                        }
+                    }
+                    Command::Quit => {
+                        self.quit(&mut terminal)?;
+                        break;
+                    }
+                },
             }
         }
         Ok(())
@@ -418,8 +1349,6 @@ Capture and resend key press as well as sending tick for refresh
 fn event_sender(tx: Sender<Event>) {
     thread::spawn(move || {
         let mut last_tick = Instant::now();
-        #[cfg(feature = "libmpv_player")]
-        let mut tick_to_playing = 20;
         loop {
             let timeout = TICK_RATE
                 .checked_sub(last_tick.elapsed())
@@ -432,17 +1361,195 @@ fn event_sender(tx: Sender<Event>) {
             }
 
             if last_tick.elapsed() >= TICK_RATE && tx.send(Event::Tick).is_ok() {
-                #[cfg(feature = "libmpv_player")]
-                {
-                    tick_to_playing -= 1;
-                    if tick_to_playing <= 0 {
-                        tick_to_playing = 4;
-                        tx.send(Event::NowPlaying).expect("could not send");
-                    }
-                }
-
                 last_tick = Instant::now();
             }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCommand, CommandLog, NullBackend};
+    use crate::config::get_app_config_path;
+    use std::path::PathBuf;
+    use std::sync::Once;
+    use std::{env, fs, process};
+
+    /**
+    App on the fixtures api and a null backend, with the log of the commands it received.
+    The user files are written in a directory of the test run
+     */
+    fn app(config: Config) -> (App, CommandLog) {
+        static HOME: Once = Once::new();
+        HOME.call_once(|| {
+            env::set_var(
+                "HOME",
+                env::temp_dir().join(format!("rrt-app-{}", process::id())),
+            );
+            env::remove_var("XDG_CONFIG_HOME");
+            env::remove_var("XDG_DATA_HOME");
+            // no icon to download
+            let icons = get_app_config_path().unwrap().join("art.msgpack");
+            fs::write(icons, rmp_serde::to_vec(&StationsArtList::new()).unwrap()).unwrap();
+        });
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/api");
+        let api = ApiClient::new(&format!("file://{}", fixtures.display()));
+        let mut app = App::new(api, config, Backend::Null);
+
+        let log = CommandLog::default();
+        let log_ = log.clone();
+        app.player = Player::with_backend(String::new(), &app.config, |state| {
+            Box::new(NullBackend::with_log(state, log_))
+        });
+        (app, log)
+    }
+
+    fn commands(log: &CommandLog) -> Vec<BackendCommand> {
+        log.lock().unwrap().drain(..).collect()
+    }
+
+    #[test]
+    fn play_station() {
+        let (mut app, log) = app(Config::default());
+        let station = app.stations_list_std[1].clone();
+        assert!(app.play_station(station.clone()));
+        assert_eq!(
+            commands(&log),
+            [BackendCommand::Play(station.stream_320.clone())]
+        );
+        assert_eq!(app.playing_station, station);
+        assert_eq!(app.get_status().state, PlaybackState::Playing);
+
+        // another station replaces the playing one
+        let other = app.stations_list_std[2].clone();
+        app.play_station(other.clone());
+        assert_eq!(
+            commands(&log),
+            [
+                BackendCommand::Stop,
+                BackendCommand::Play(other.stream_320.clone())
+            ]
+        );
+    }
+
+    #[test]
+    fn toggle_quality_plays_the_station_again() {
+        let (mut app, log) = app(Config::default());
+        let station = app.playing_station.clone();
+        app.play_station(station.clone());
+        app.toggle_quality();
+        assert_eq!(
+            commands(&log),
+            [
+                BackendCommand::Play(station.stream_320.clone()),
+                BackendCommand::Stop,
+                BackendCommand::Play(station.stream_128.clone())
+            ]
+        );
+        assert_eq!(app.get_status().quality, Quality::Medium);
+    }
+
+    #[test]
+    fn status_shows_the_streamed_quality() {
+        let config = Config {
+            quality: Quality::Hls,
+            ..Config::default()
+        };
+        let (mut app, log) = app(config);
+        // no HLS stream for this one
+        let station = app.stations_list_std[2].clone();
+        app.play_station(station.clone());
+        assert_eq!(
+            commands(&log),
+            [BackendCommand::Play(station.stream_320.clone())]
+        );
+        assert_eq!(app.get_status().quality, Quality::High);
+    }
+
+    #[test]
+    fn sleep_timer_stops_the_playback() {
+        let (mut app, log) = app(Config::default());
+        app.play_station(app.playing_station.clone());
+        app.set_volume(40);
+        app.sleep_timer = Some(SleepTimer::new(Duration::ZERO, 40));
+        app.update_sleep_timer();
+        assert_eq!(
+            commands(&log),
+            [
+                BackendCommand::Play(app.playing_station.stream_320.clone()),
+                BackendCommand::Volume(40),
+                BackendCommand::Stop,
+                BackendCommand::Volume(40)
+            ]
+        );
+        assert!(app.sleep_timer.is_none());
+    }
+
+    #[test]
+    fn track_heard_ends_when_the_playback_stops() {
+        let (mut app, _log) = app(Config::default());
+        let station = app
+            .stations_list_std
+            .iter()
+            .find(|station| station.id == 15016)
+            .unwrap()
+            .clone();
+        app.play_station(station);
+        app.update_now_playing();
+        assert!(app.history.recent()[0].end.is_none());
+        app.toggle_play();
+        assert!(app.history.recent()[0].end.is_some());
+
+        app.player.resume();
+        app.update_now_playing();
+        assert!(app.history.recent()[0].end.is_none());
+        app.sleep_timer = Some(SleepTimer::new(Duration::ZERO, 40));
+        app.update_sleep_timer();
+        assert!(app.history.recent()[0].end.is_some());
+    }
+
+    #[test]
+    fn toggle_recording() {
+        let dir = env::temp_dir().join(format!("rrt-record-{}", process::id()));
+        let config = Config {
+            record_dir: Some(dir.clone()),
+            ..Config::default()
+        };
+        let (mut app, log) = app(config);
+        app.play_station(app.playing_station.clone());
+        app.toggle_recording();
+        app.toggle_recording();
+        match &commands(&log)[..] {
+            [BackendCommand::Play(_), BackendCommand::Record(Some(path)), BackendCommand::Record(None)] =>
+            {
+                assert!(path.starts_with(&dir))
+            }
+            commands => panic!("unexpected commands {:?}", commands),
+        }
+        assert_eq!(app.music_title, "Recording stopped");
+    }
+
+    #[test]
+    fn mpris_track_given_by_the_api() {
+        let (mut app, _log) = app(Config::default());
+        let station = app
+            .stations_list_std
+            .iter()
+            .find(|station| station.id == 15016)
+            .unwrap()
+            .clone();
+        app.play_station(station);
+        // the null backend sends no stream title
+        app.update_now_playing();
+        let track = Title {
+            song: "Blah Blah Blah".to_string(),
+            artist: "Armin van Buuren".to_string(),
+        };
+        assert_eq!(app.music_title, track.to_string());
+        assert_eq!(app.mpris_snapshot().track, Some(track));
+
+        app.player.stop();
+        assert_eq!(app.mpris_snapshot().track, None);
+    }
+}
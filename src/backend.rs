@@ -0,0 +1,245 @@
+use crate::api::Quality;
+use crate::config::Config;
+use crate::daemon_backend::DaemonBackend;
+use crate::player::{PlaybackState, PlayerEvent};
+use clap::ValueEnum;
+use crossbeam::channel::Sender;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "libmpv_player")]
+use crate::mpv_backend::MpvBackend;
+#[cfg(feature = "rodio_player")]
+use crate::rodio_backend::RodioBackend;
+
+/// Time left to a backend writing the recorded file in another thread to close it
+pub const RECORD_CLOSE_GRACE: Duration = Duration::from_secs(2);
+
+/**
+Audio output used by the player. Every command returns at once, what really happens to the stream is
+reported through the shared state
+ */
+pub trait AudioBackend: Send {
+    /// Open the url, replacing the current stream
+    fn play(&mut self, url: &str);
+    fn stop(&mut self);
+    fn pause(&mut self);
+    fn resume(&mut self);
+    /// Volume between 0 and 100
+    fn set_volume(&mut self, volume: u8);
+    fn set_mute(&mut self, muted: bool);
+    /// Write the played stream to a file as well, stop recording if None
+    fn record(&self, path: Option<PathBuf>) -> io::Result<()>;
+    /// Time the previous recorded file may still be written once `record` returned
+    fn record_close_delay(&self) -> Duration {
+        Duration::ZERO
+    }
+    /// Title sent in the stream metadata, None if the backend can not read it
+    fn now_playing(&self) -> Option<String> {
+        None
+    }
+}
+
+/**
+Backends which can be chosen with the `backend` config key or the `--backend` option
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// libmpv, needs the libmpv_player feature
+    Mpv,
+    /// rodio, needs the rodio_player feature
+    Rodio,
+    /// No audio output, the streams are never opened
+    Null,
+    /// Commands sent to the running daemon, chosen when the interface starts
+    #[value(skip)]
+    #[serde(skip)]
+    Daemon,
+}
+
+impl Default for Backend {
+    /**
+    The best backend compiled in
+     */
+    fn default() -> Self {
+        if cfg!(feature = "libmpv_player") {
+            Backend::Mpv
+        } else if cfg!(feature = "rodio_player") {
+            Backend::Rodio
+        } else {
+            Backend::Null
+        }
+    }
+}
+
+impl Backend {
+    /**
+    The backend itself if it was compiled in, the default one otherwise
+     */
+    fn compiled(self) -> Self {
+        match self {
+            Backend::Mpv if !cfg!(feature = "libmpv_player") => Backend::default(),
+            Backend::Rodio if !cfg!(feature = "rodio_player") => Backend::default(),
+            backend => backend,
+        }
+    }
+
+    /**
+    Quality to ask for when the given one is preferred, rodio can not play HLS playlists
+     */
+    pub fn playable(self, quality: Quality) -> Quality {
+        match (self.compiled(), quality) {
+            (Backend::Rodio, Quality::Hls) => Quality::High,
+            _ => quality,
+        }
+    }
+}
+
+/**
+Create the chosen backend, or the default one if it was not compiled in
+ */
+#[cfg_attr(
+    not(any(feature = "libmpv_player", feature = "rodio_player")),
+    allow(unused_variables)
+)]
+pub fn new_backend(backend: Backend, state: SharedState, config: &Config) -> Box<dyn AudioBackend> {
+    match backend.compiled() {
+        #[cfg(feature = "libmpv_player")]
+        Backend::Mpv => Box::new(MpvBackend::new(state, config)),
+        #[cfg(feature = "rodio_player")]
+        Backend::Rodio => Box::new(RodioBackend::new(state, config)),
+        Backend::Daemon => Box::new(DaemonBackend::new(state)),
+        // compiled() never gives a backend left out of the build
+        _ => Box::new(NullBackend::new(state)),
+    }
+}
+
+/**
+Playback state shared between the player and its backend, every change is sent to the app
+ */
+#[derive(Clone)]
+pub struct SharedState {
+    state: Arc<Mutex<PlaybackState>>,
+    events: Sender<PlayerEvent>,
+}
+
+impl SharedState {
+    pub fn new(events: Sender<PlayerEvent>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PlaybackState::Stopped)),
+            events,
+        }
+    }
+
+    pub fn get(&self) -> PlaybackState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, new: PlaybackState) {
+        let mut state = self.state.lock().unwrap();
+        if *state != new {
+            *state = new.clone();
+            self.send(PlayerEvent::State(new));
+        }
+    }
+
+    /**
+    Notify the app without ever blocking the backend, events are dropped if nobody listens
+     */
+    pub fn send(&self, event: PlayerEvent) {
+        let _ = self.events.try_send(event);
+    }
+}
+
+/**
+Command received by the null backend
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendCommand {
+    Play(String),
+    Stop,
+    Pause,
+    Resume,
+    Volume(u8),
+    Mute(bool),
+    Record(Option<PathBuf>),
+}
+
+/// Commands received by a null backend, the oldest first
+pub type CommandLog = Arc<Mutex<Vec<BackendCommand>>>;
+
+/**
+Silent backend, every command succeeds at once. Used to run the app without audio hardware.
+The commands can be kept in a log, to check what the app asked for
+ */
+pub struct NullBackend {
+    state: SharedState,
+    log: Option<CommandLog>,
+}
+
+impl NullBackend {
+    pub fn new(state: SharedState) -> Self {
+        Self { state, log: None }
+    }
+
+    /**
+    Null backend writing the commands to a log shared with the caller, for the tests
+     */
+    #[cfg(test)]
+    pub fn with_log(state: SharedState, log: CommandLog) -> Self {
+        Self {
+            state,
+            log: Some(log),
+        }
+    }
+
+    fn issued(&self, command: BackendCommand) {
+        if let Some(log) = &self.log {
+            log.lock().unwrap().push(command);
+        }
+    }
+}
+
+impl AudioBackend for NullBackend {
+    fn play(&mut self, url: &str) {
+        self.issued(BackendCommand::Play(url.to_string()));
+        self.state.set(PlaybackState::Playing);
+    }
+
+    fn stop(&mut self) {
+        self.issued(BackendCommand::Stop);
+        self.state.set(PlaybackState::Stopped);
+    }
+
+    fn pause(&mut self) {
+        self.issued(BackendCommand::Pause);
+        if self.state.get() == PlaybackState::Playing {
+            self.state.set(PlaybackState::Paused);
+        }
+    }
+
+    fn resume(&mut self) {
+        self.issued(BackendCommand::Resume);
+        if self.state.get() == PlaybackState::Paused {
+            self.state.set(PlaybackState::Playing);
+        }
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        self.issued(BackendCommand::Volume(volume));
+        self.state.send(PlayerEvent::Volume(volume));
+    }
+
+    fn set_mute(&mut self, muted: bool) {
+        self.issued(BackendCommand::Mute(muted));
+    }
+
+    fn record(&self, path: Option<PathBuf>) -> io::Result<()> {
+        self.issued(BackendCommand::Record(path));
+        Ok(())
+    }
+}
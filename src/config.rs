@@ -1,5 +1,9 @@
-use crate::api::Station;
+use crate::alarm::Alarm;
+use crate::api::{Quality, Station};
+use crate::backend::Backend;
 use crate::config::Error::ReadConfig;
+use crate::navigation::NextPolicy;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::{fs, io};
 use thiserror::Error;
@@ -16,6 +20,76 @@ pub enum Error {
     ReadConfig(),
 }
 /**
+User settings stored in the config file. Every field is optional
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Base url of the radio record api
+    pub api_url: Option<String>,
+    /// Preferred stream quality
+    pub quality: Quality,
+    /// Attempts to reconnect a dropped stream before giving up
+    pub reconnect_attempts: u32,
+    /// Last volume used, between 0 and 100
+    pub volume: u8,
+    /// Directory of the recordings
+    pub record_dir: Option<PathBuf>,
+    /// Start a new recording file on each track
+    pub record_split: bool,
+    /// Audio backend, the default is the best one compiled in
+    pub backend: Backend,
+    /// Show a desktop notification when the title or the station changes
+    pub notifications: bool,
+    /// Minimum seconds between two notifications
+    pub notification_interval: u64,
+    /// Station played by Next in headless mode
+    pub next: NextPolicy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            api_url: None,
+            quality: Quality::default(),
+            reconnect_attempts: 5,
+            volume: 85,
+            record_dir: None,
+            record_split: true,
+            backend: Backend::default(),
+            notifications: false,
+            notification_interval: 10,
+            next: NextPolicy::default(),
+        }
+    }
+}
+
+/**
+Read the config file or return the default config
+ */
+pub fn read_config() -> Result<Config, Error> {
+    let mut path = get_app_config_path()?;
+    path.push("config.json");
+
+    if !path.exists() {
+        Ok(Config::default())
+    } else {
+        let content = fs::read_to_string(path)?;
+        let parsed: Config = serde_json::from_str(&content)?;
+        Ok(parsed)
+    }
+}
+/**
+Save the config file
+ */
+pub fn write_config(config: &Config) -> Result<(), Error> {
+    let mut path = get_app_config_path()?;
+    path.push("config.json");
+
+    fs::write(path, serde_json::to_vec_pretty(config)?)?;
+    Ok(())
+}
+/**
 Add or delete a favorite from the favorite file
  */
 pub fn toggle_to_favorite(station: &Station) -> Result<Vec<Station>, Error> {
@@ -49,6 +123,56 @@ pub fn read_favorite() -> Result<Vec<Station>, Error> {
         Ok(parsed)
     }
 }
+/**
+Read the stations list saved on the last successful fetch or return an empty list
+ */
+pub fn read_stations_cache() -> Result<Vec<Station>, Error> {
+    let mut path = get_app_config_path()?;
+    path.push("stations.json");
+
+    if !path.exists() {
+        Ok(Vec::new())
+    } else {
+        let content = fs::read_to_string(path)?;
+        let parsed: Vec<Station> = serde_json::from_str(&content)?;
+        Ok(parsed)
+    }
+}
+/**
+Save the stations list so it can be used when the api is unreachable
+ */
+pub fn write_stations_cache(list: &[Station]) -> Result<(), Error> {
+    let mut path = get_app_config_path()?;
+    path.push("stations.json");
+
+    fs::write(path, serde_json::to_vec(list)?)?;
+    Ok(())
+}
+/**
+Read the alarms file or return an empty list
+ */
+pub fn read_alarms() -> Result<Vec<Alarm>, Error> {
+    let mut path = get_app_config_path()?;
+    path.push("alarms.json");
+
+    if !path.exists() {
+        Ok(Vec::new())
+    } else {
+        let content = fs::read_to_string(path)?;
+        let parsed: Vec<Alarm> = serde_json::from_str(&content)?;
+        Ok(parsed)
+    }
+}
+/**
+Save the alarms
+ */
+pub fn write_alarms(alarms: &[Alarm]) -> Result<(), Error> {
+    let mut path = get_app_config_path()?;
+    path.push("alarms.json");
+
+    fs::write(path, serde_json::to_string_pretty(alarms)?)?;
+    Ok(())
+}
 
 pub fn get_app_config_path() -> Result<PathBuf, Error> {
     let mut path = dirs_next::config_dir().ok_or(ReadConfig())?;
@@ -59,3 +183,16 @@ pub fn get_app_config_path() -> Result<PathBuf, Error> {
     }
     Ok(path)
 }
+
+/**
+Directory of the data kept by the application, like the listening history
+ */
+pub fn get_app_data_path() -> Result<PathBuf, Error> {
+    let mut path = dirs_next::data_dir().ok_or(ReadConfig())?;
+    path.push("radiorecord-tui");
+
+    if !path.exists() {
+        fs::create_dir_all(&path)?;
+    }
+    Ok(path)
+}
@@ -0,0 +1,323 @@
+use crate::api::Station;
+use crate::player::PlaybackState;
+use clap::{ArgAction, Subcommand};
+use crossbeam::channel::{self, Sender};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs, thread};
+
+/// Longest wait for the daemon to answer a request
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+Command sent to the daemon, as one JSON object per line like `{"command":"volume","volume":50}`
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, Subcommand)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    /// Play a station, given by its prefix or one of its stream urls. Resume the last one if none
+    Play { station: Option<String> },
+    /// Stop the playback
+    Stop,
+    /// Pause the playback
+    Pause,
+    /// Resume the playback
+    Resume,
+    /// Play the next station
+    Next,
+    /// Play the station played before
+    Previous,
+    /// Set the volume, between 0 and 100
+    Volume { volume: u8 },
+    /// Mute or unmute the playback
+    Mute {
+        #[arg(action = ArgAction::Set)]
+        muted: bool,
+    },
+    /// Record the stream to a file, stop recording if none
+    Record { path: Option<PathBuf> },
+    /// Show the state of the player
+    Status,
+    /// Show the title being played
+    NowPlaying,
+}
+
+/**
+Answer of the daemon to a request, one JSON object per line
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "reply", rename_all = "kebab-case")]
+pub enum Reply {
+    Ok,
+    Status {
+        station: Box<Station>,
+        state: PlaybackState,
+        /// Title sent in the stream metadata
+        title: Option<String>,
+        volume: u8,
+        muted: bool,
+    },
+    NowPlaying {
+        title: Option<String>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Request received on the socket, and where to send its reply
+pub type Control = (Request, Sender<Reply>);
+
+/**
+Path of the control socket, in the runtime directory if there is one
+ */
+pub fn socket_path() -> PathBuf {
+    dirs_next::runtime_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join("rrt.sock")
+}
+
+/**
+A daemon answers on the control socket
+ */
+pub fn is_running() -> bool {
+    UnixStream::connect(socket_path()).is_ok()
+}
+
+/**
+Listen on the control socket, a socket left by a daemon which did not stop properly is replaced
+ */
+pub fn bind() -> io::Result<UnixListener> {
+    let path = socket_path();
+    if path.exists() {
+        if is_running() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "a daemon is already running",
+            ));
+        }
+        fs::remove_file(&path)?;
+    }
+    UnixListener::bind(path)
+}
+
+/**
+Accept the clients forever, their requests are sent to the player with a channel for the reply
+ */
+pub fn serve(listener: UnixListener, controls: Sender<Control>) {
+    for stream in listener.incoming().flatten() {
+        let controls = controls.clone();
+        thread::spawn(move || {
+            let mut writer = match stream.try_clone() {
+                Ok(writer) => writer,
+                Err(_) => return,
+            };
+            for line in BufReader::new(stream).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => return,
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let reply = match serde_json::from_str(&line) {
+                    Ok(request) => {
+                        let (tx, rx) = channel::bounded(1);
+                        match controls.send((request, tx)) {
+                            Ok(()) => rx.recv().unwrap_or(Reply::Error {
+                                message: "The player stopped".to_string(),
+                            }),
+                            Err(_) => return,
+                        }
+                    }
+                    Err(error) => Reply::Error {
+                        message: error.to_string(),
+                    },
+                };
+                let mut json = serde_json::to_vec(&reply).unwrap();
+                json.push(b'\n');
+                if writer.write_all(&json).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+/**
+Send a request to the running daemon and wait for its reply
+ */
+pub fn request(request: &Request) -> io::Result<Reply> {
+    send(&socket_path(), request)
+}
+
+/**
+Send a request on a control socket, one line each way
+ */
+fn send(path: &Path, request: &Request) -> io::Result<Reply> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut json = serde_json::to_vec(request)?;
+    json.push(b'\n');
+    stream.write_all(&json)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{ApiClient, Quality};
+    use crate::backend::Backend;
+    use crate::config::Config;
+    use crate::headless::Headless;
+    use crate::mpris::tests::TestBus;
+    use std::process;
+
+    /**
+    Json line of a message, read back and written again
+     */
+    fn round_trip<T: Serialize + serde::de::DeserializeOwned>(message: &T) -> (String, String) {
+        let json = serde_json::to_string(message).unwrap();
+        let parsed: T = serde_json::from_str(&json).unwrap();
+        (json, serde_json::to_string(&parsed).unwrap())
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        let requests = [
+            Request::Play {
+                station: Some("rr_main".to_string()),
+            },
+            Request::Play { station: None },
+            Request::Stop,
+            Request::Pause,
+            Request::Resume,
+            Request::Next,
+            Request::Previous,
+            Request::Volume { volume: 50 },
+            Request::Mute { muted: true },
+            Request::Record {
+                path: Some(PathBuf::from("/tmp/record.mp3")),
+            },
+            Request::Record { path: None },
+            Request::Status,
+            Request::NowPlaying,
+        ];
+        for request in &requests {
+            let (json, again) = round_trip(request);
+            assert_eq!(json, again);
+        }
+
+        // as written by hand
+        assert!(matches!(
+            serde_json::from_str(r#"{"command":"volume","volume":50}"#),
+            Ok(Request::Volume { volume: 50 })
+        ));
+        assert!(matches!(
+            serde_json::from_str(r#"{"command":"now-playing"}"#),
+            Ok(Request::NowPlaying)
+        ));
+        assert!(serde_json::from_str::<Request>(r#"{"command":"rewind"}"#).is_err());
+    }
+
+    #[test]
+    fn replies_round_trip() {
+        let mut station = Station::default();
+        station.id = 15016;
+        station.prefix = "rr_main".to_string();
+        let replies = [
+            Reply::Ok,
+            Reply::Status {
+                station: Box::new(station),
+                state: PlaybackState::Reconnecting { attempt: 2, max: 5 },
+                title: Some("Armin van Buuren - Blah Blah Blah".to_string()),
+                volume: 40,
+                muted: false,
+            },
+            Reply::NowPlaying { title: None },
+            Reply::Error {
+                message: "Station not found".to_string(),
+            },
+        ];
+        for reply in &replies {
+            let (json, again) = round_trip(reply);
+            assert_eq!(json, again);
+        }
+        assert_eq!(
+            serde_json::to_string(&Reply::Ok).unwrap(),
+            r#"{"reply":"ok"}"#
+        );
+    }
+
+    fn status(path: &Path) -> (Station, PlaybackState) {
+        match send(path, &Request::Status).unwrap() {
+            Reply::Status { station, state, .. } => (*station, state),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn daemon_on_a_socket() {
+        // the MPRIS server of the daemon is on a private bus
+        let bus = TestBus::start();
+        env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address);
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/api");
+        let list = ApiClient::new(&format!("file://{}", fixtures.display()))
+            .stations_list()
+            .unwrap();
+        let headless = Headless::new(
+            list.clone(),
+            Config::default(),
+            Backend::Null,
+            Quality::High,
+            None,
+            list[0].clone(),
+        )
+        .await
+        .unwrap();
+
+        let path = env::temp_dir().join(format!("rrt-{}.sock", process::id()));
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let (controls, received) = channel::bounded(1);
+        thread::spawn(move || headless.run(received));
+        thread::spawn(move || serve(listener, controls));
+
+        let station = list[1].clone();
+        let play = Request::Play {
+            station: Some(station.prefix.clone()),
+        };
+        assert!(matches!(send(&path, &play), Ok(Reply::Ok)));
+        assert_eq!(status(&path), (station.clone(), PlaybackState::Playing));
+
+        // a failed request is told to the client, the playback goes on
+        let unknown = Request::Play {
+            station: Some("unknown".to_string()),
+        };
+        match send(&path, &unknown).unwrap() {
+            Reply::Error { message } => assert_eq!(message, "Station not found"),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        assert_eq!(status(&path), (station.clone(), PlaybackState::Playing));
+
+        assert!(matches!(send(&path, &Request::Pause), Ok(Reply::Ok)));
+        assert_eq!(status(&path), (station, PlaybackState::Paused));
+
+        // a line which is not a request
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.write_all(b"{\"command\":\"rewind\"}\n").unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert!(matches!(
+            serde_json::from_str(&line),
+            Ok(Reply::Error { .. })
+        ));
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::backend::{AudioBackend, SharedState, RECORD_CLOSE_GRACE};
+use crate::daemon::{self, Reply, Request};
+use crate::player::{PlaybackState, PlayerEvent};
+use crossbeam::channel::{self, Sender};
+use crossbeam::select;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Delay between two status requests
+const POLL: Duration = Duration::from_secs(1);
+const NO_ANSWER: &str = "The daemon does not answer";
+
+/**
+Backend forwarding the commands to the running daemon, which plays the streams.
+The state of the daemon is polled and reported like the other backends do
+ */
+pub struct DaemonBackend {
+    state: SharedState,
+    /// Requests for the daemon, with where to send the reply if it is awaited
+    requests: Sender<(Request, Option<Sender<Reply>>)>,
+    title: Arc<Mutex<Option<String>>>,
+}
+
+impl DaemonBackend {
+    pub fn new(state: SharedState) -> Self {
+        let (requests, receiver) = channel::unbounded();
+        let title = Arc::new(Mutex::new(None));
+
+        // the requests are sent in order, away from the app. The thread ends with the backend
+        let title_ = title.clone();
+        let state_ = state.clone();
+        thread::spawn(move || {
+            let (title, state) = (title_, state_);
+            let mut volume = None;
+            loop {
+                let (request, done): (Request, Option<Sender<Reply>>) = select! {
+                    recv(receiver) -> request => match request {
+                        Ok(request) => request,
+                        Err(_) => return,
+                    },
+                    default(POLL) => (Request::Status, None),
+                };
+                let reply = daemon::request(&request).unwrap_or_else(|_| Reply::Error {
+                    message: NO_ANSWER.to_string(),
+                });
+                match &reply {
+                    Reply::Status {
+                        state: new_state,
+                        title: new_title,
+                        volume: new_volume,
+                        ..
+                    } => {
+                        state.set(new_state.clone());
+                        let mut title = title.lock().unwrap();
+                        if let Some(new_title) =
+                            new_title.clone().filter(|t| title.as_ref() != Some(t))
+                        {
+                            state.send(PlayerEvent::Title(new_title.clone()));
+                            *title = Some(new_title);
+                        }
+                        if volume != Some(*new_volume) {
+                            volume = Some(*new_volume);
+                            state.send(PlayerEvent::Volume(*new_volume));
+                        }
+                    }
+                    // the other requests failing leave the playback as it is, the caller is told
+                    Reply::Error { message } if matches!(request, Request::Status) => {
+                        state.set(PlaybackState::Error(message.clone()))
+                    }
+                    _ => {}
+                }
+                if let Some(done) = done {
+                    let _ = done.send(reply);
+                }
+            }
+        });
+
+        Self {
+            state,
+            requests,
+            title,
+        }
+    }
+
+    fn send(&self, request: Request) {
+        let _ = self.requests.send((request, None));
+    }
+}
+
+impl AudioBackend for DaemonBackend {
+    fn play(&mut self, url: &str) {
+        *self.title.lock().unwrap() = None;
+        self.state.set(PlaybackState::Buffering);
+        self.send(Request::Play {
+            station: Some(url.to_string()),
+        });
+    }
+
+    // the state is corrected by the next status of the daemon
+    fn stop(&mut self) {
+        self.state.set(PlaybackState::Stopped);
+        self.send(Request::Stop);
+    }
+
+    fn pause(&mut self) {
+        if self.state.get() == PlaybackState::Playing {
+            self.state.set(PlaybackState::Paused);
+        }
+        self.send(Request::Pause);
+    }
+
+    fn resume(&mut self) {
+        if self.state.get() == PlaybackState::Paused {
+            self.state.set(PlaybackState::Playing);
+        }
+        self.send(Request::Resume);
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        self.send(Request::Volume { volume });
+    }
+
+    fn set_mute(&mut self, muted: bool) {
+        self.send(Request::Mute { muted });
+    }
+
+    fn record(&self, path: Option<PathBuf>) -> io::Result<()> {
+        let (done, reply) = channel::bounded(1);
+        let _ = self.requests.send((Request::Record { path }, Some(done)));
+        match reply.recv() {
+            Ok(Reply::Error { message }) => Err(io::Error::other(message)),
+            Ok(_) => Ok(()),
+            Err(_) => Err(io::Error::other(NO_ANSWER)),
+        }
+    }
+
+    // the backend of the daemon is not known
+    fn record_close_delay(&self) -> Duration {
+        RECORD_CLOSE_GRACE
+    }
+
+    fn now_playing(&self) -> Option<String> {
+        self.title.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,291 @@
+use crate::api::{list_genres, Quality, Station, Title};
+use crate::app::Event;
+use crate::backend::Backend;
+use crate::config::{read_favorite, write_config, Config};
+use crate::daemon::{Control, Reply, Request};
+use crate::mpris::{self, launch_mpris_server, player_volume, MprisServer, Playlist, Snapshot};
+use crate::navigation::{Navigation, NextPolicy};
+use crate::notifier::Notifier;
+use crate::player::{PlaybackState, Player, PlayerEvent};
+use crate::timer::SleepTimer;
+use crossbeam::channel::{self, Receiver};
+use crossbeam::select;
+use std::process::exit;
+use std::time::Duration;
+
+/**
+Player without interface, controlled through MPRIS and the daemon socket. The changes are printed on stdout
+ */
+pub struct Headless {
+    player: Player,
+    playing: Station,
+    list: Vec<Station>,
+    favorites: Vec<Station>,
+    playlists: Vec<Playlist>,
+    navigation: Navigation,
+    config: Config,
+    backend: Backend,
+    quality: Quality,
+    sleep_timer: Option<SleepTimer>,
+    notifier: Option<Notifier>,
+    mpris: MprisServer,
+    mpris_events: Receiver<Event>,
+}
+
+impl Headless {
+    /**
+    Prepare the player on a station, nothing is played yet
+     */
+    pub async fn new(
+        list: Vec<Station>,
+        config: Config,
+        backend: Backend,
+        quality: Quality,
+        next: Option<NextPolicy>,
+        station: Station,
+    ) -> color_eyre::Result<Self> {
+        let quality = backend.playable(quality);
+        let player = Player::new(station.stream_url(quality).to_string(), &config, backend);
+        // launch and handle mpris interface
+        let (mpris_tx, mpris_events) = channel::unbounded();
+        let mpris = launch_mpris_server(mpris_tx).await?;
+        let notifier = Notifier::new(&config).await;
+        let favorites = read_favorite().unwrap_or_default();
+        let playlists = Playlist::list(&favorites, list_genres(&list));
+
+        Ok(Self {
+            player,
+            playing: station,
+            list,
+            favorites,
+            playlists,
+            navigation: Navigation::new(next.unwrap_or(config.next)),
+            config,
+            backend,
+            quality,
+            sleep_timer: None,
+            notifier,
+            mpris,
+            mpris_events,
+        })
+    }
+
+    /**
+    Stop the playback after a duration, the volume fading out before
+     */
+    pub fn set_sleep(&mut self, sleep: Duration) {
+        self.sleep_timer = Some(SleepTimer::new(sleep, self.player.volume()));
+    }
+
+    /**
+    Play the station, at the quality in use
+     */
+    pub fn tune(&mut self, station: Station) {
+        self.playing = station;
+        println!("Now playing : {}", self.playing.title);
+        self.player
+            .force_play(self.playing.stream_url(self.quality));
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(&self.playing.title, "", &self.playing);
+        }
+    }
+
+    /**
+    Handle the events until the process exits. The controls come from the daemon socket
+     */
+    pub fn run(mut self, controls: Receiver<Control>) {
+        let player_events = self.player.events();
+        loop {
+            self.publish_mpris();
+
+            if let Some(timer) = &self.sleep_timer {
+                if timer.is_over() {
+                    self.player.stop();
+                    self.player.set_volume(timer.initial_volume());
+                    self.sleep_timer = None;
+                    println!("Sleep timer over, playback stopped");
+                } else if timer.volume() != self.player.volume() {
+                    self.player.set_volume(timer.volume());
+                }
+            }
+            // wake up every second while the sleep timer runs, otherwise only on events
+            let timer_tick = match self.sleep_timer {
+                Some(_) => channel::after(Duration::from_secs(1)),
+                None => channel::never(),
+            };
+            select! {
+                recv(self.mpris_events) -> event => {
+                    if let Ok(Event::Mpris(command)) = event {
+                        self.mpris_command(command);
+                    }
+                }
+                recv(controls) -> control => {
+                    if let Ok((request, reply)) = control {
+                        let _ = reply.send(self.request(request));
+                    }
+                }
+                recv(player_events) -> event => match event.unwrap() {
+                    PlayerEvent::Title(title) => {
+                        println!("{}", title);
+                        if let Some(notifier) = &self.notifier {
+                            if Title::from_stream_title(&title).is_some() {
+                                notifier.notify(&title, &self.playing.title, &self.playing);
+                            }
+                        }
+                    }
+                    PlayerEvent::State(PlaybackState::Error(error)) => {
+                        eprintln!("{}", error);
+                    }
+                    _ => {}
+                },
+                recv(timer_tick) -> _ => {}
+            }
+        }
+    }
+
+    /**
+    The MPRIS clients read the state left by the last event
+     */
+    fn publish_mpris(&self) {
+        let state = self.player.state();
+        let title = self
+            .player
+            .now_playing()
+            .unwrap_or_else(|| self.playing.title.clone());
+        let track = Title::from_stream_title(&title);
+        self.mpris.publish(Snapshot {
+            title: state.mpris_title(&title),
+            track: state.mpris_track(track.as_ref()),
+            station: Some(self.playing.clone()),
+            status: state.mpris_status(),
+            volume: self.player.volume() as f64 / 100.0,
+            sleep: self.sleep_timer.as_ref().map(SleepTimer::end),
+            playlists: self.playlists.clone(),
+            active_playlist: self.navigation.playlist().cloned(),
+        });
+    }
+
+    fn mpris_command(&mut self, command: mpris::Command) {
+        match command {
+            mpris::Command::PlayPause => self.player.toggle_play(),
+            mpris::Command::Pause => self.player.pause(),
+            mpris::Command::Stop => self.player.stop(),
+            mpris::Command::Play => self.player.resume(),
+            mpris::Command::Next => self.next(),
+            mpris::Command::Previous => self.previous(),
+            mpris::Command::ActivatePlaylist(path) => {
+                if let Some(found) = self.playlists.iter().find(|p| p.path() == path) {
+                    let stations = found.stations(&self.list, &self.favorites);
+                    let first = self
+                        .navigation
+                        .activate(found.clone(), stations, &self.playing);
+                    if let Some(station) = first {
+                        self.tune(station);
+                    }
+                }
+            }
+            mpris::Command::SetVolume(volume) => self.set_volume(player_volume(volume)),
+            mpris::Command::Quit => {
+                self.player.stop();
+                exit(0);
+            }
+        }
+    }
+
+    fn request(&mut self, request: Request) -> Reply {
+        match request {
+            Request::Play { station: None } => self.player.resume(),
+            Request::Play {
+                station: Some(station),
+            } => match self.find_station(&station) {
+                Some((station, quality)) => {
+                    self.navigation.leave(&self.playing);
+                    self.quality = self.backend.playable(quality);
+                    self.tune(station);
+                }
+                None => {
+                    return Reply::Error {
+                        message: "Station not found".to_string(),
+                    }
+                }
+            },
+            Request::Stop => self.player.stop(),
+            Request::Pause => self.player.pause(),
+            Request::Resume => self.player.resume(),
+            Request::Next => self.next(),
+            Request::Previous => self.previous(),
+            Request::Volume { volume } => self.set_volume(volume.min(100)),
+            Request::Mute { muted } => {
+                if self.player.is_muted() != muted {
+                    self.player.toggle_mute();
+                }
+            }
+            Request::Record { path } => {
+                if let Err(error) = self.player.record(path) {
+                    return Reply::Error {
+                        message: error.to_string(),
+                    };
+                }
+            }
+            Request::Status => {
+                return Reply::Status {
+                    station: Box::new(self.playing.clone()),
+                    state: self.player.state(),
+                    title: self.player.now_playing(),
+                    volume: self.player.volume(),
+                    muted: self.player.is_muted(),
+                }
+            }
+            Request::NowPlaying => {
+                return Reply::NowPlaying {
+                    title: self.player.is_playing().then(|| {
+                        self.player
+                            .now_playing()
+                            .unwrap_or_else(|| self.playing.title.clone())
+                    }),
+                }
+            }
+        }
+        Reply::Ok
+    }
+
+    /**
+    Station by its prefix, or by one of its stream urls with the matching quality
+     */
+    fn find_station(&self, station: &str) -> Option<(Station, Quality)> {
+        self.list.iter().find_map(|s| {
+            if s.prefix == station {
+                return Some((s.clone(), self.quality));
+            }
+            Quality::ALL
+                .into_iter()
+                .find(|quality| s.stream_url(*quality) == station)
+                .map(|quality| (s.clone(), quality))
+        })
+    }
+
+    fn next(&mut self) {
+        let station = self
+            .navigation
+            .next(&self.list, &self.favorites, &self.playing);
+        self.tune(station);
+    }
+
+    fn previous(&mut self) {
+        if let Some(station) = self.navigation.previous(&self.playing) {
+            self.tune(station);
+        }
+    }
+
+    /**
+    Set the volume and keep it for the next time. A running sleep timer fades out from it
+     */
+    fn set_volume(&mut self, volume: u8) {
+        self.player.set_volume(volume);
+        self.config.volume = self.player.volume();
+        let _ = write_config(&self.config);
+        if let Some(timer) = &mut self.sleep_timer {
+            timer.set_volume(self.config.volume);
+        }
+    }
+}
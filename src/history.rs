@@ -0,0 +1,124 @@
+use crate::api::Title;
+use crate::config::{get_app_data_path, Error};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Tracks kept in memory for the recently heard pane
+const RECENT: usize = 200;
+
+/**
+A track heard on a station
+ */
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Track {
+    /// Id of the station
+    pub station: usize,
+    pub artist: String,
+    pub song: String,
+    pub start: DateTime<Local>,
+    pub end: Option<DateTime<Local>>,
+}
+
+impl Track {
+    fn is(&self, station: usize, title: &Title) -> bool {
+        self.station == station && self.artist == title.artist && self.song == title.song
+    }
+}
+
+/**
+Remember the tracks heard, each one is saved in the history file once it is over
+ */
+pub struct History {
+    current: Option<Track>,
+    recent: Vec<Track>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        let mut recent = read_history().unwrap_or_default();
+        recent.drain(..recent.len().saturating_sub(RECENT));
+        Self {
+            current: None,
+            recent,
+        }
+    }
+
+    /**
+    A title is playing on the station, start a new track if it changed
+     */
+    pub fn heard(&mut self, station: usize, title: Title) {
+        if let Some(current) = &self.current {
+            if current.is(station, &title) {
+                return;
+            }
+        }
+        self.finish();
+        self.current = Some(Track {
+            station,
+            artist: title.artist,
+            song: title.song,
+            start: Local::now(),
+            end: None,
+        });
+    }
+
+    /**
+    The current track is over (changed, stopped or the program quits), save it
+     */
+    pub fn finish(&mut self) {
+        if let Some(mut track) = self.current.take() {
+            track.end = Some(Local::now());
+            let _ = append_history(&track);
+            self.recent.push(track);
+            if self.recent.len() > RECENT {
+                self.recent.remove(0);
+            }
+        }
+    }
+
+    /**
+    Tracks heard, the most recent first
+     */
+    pub fn recent(&self) -> Vec<&Track> {
+        self.current
+            .iter()
+            .chain(self.recent.iter().rev())
+            .collect()
+    }
+}
+
+fn history_path() -> Result<PathBuf, Error> {
+    let mut path = get_app_data_path()?;
+    path.push("history.jsonl");
+    Ok(path)
+}
+
+/**
+Add a track at the end of the history file, one json object per line
+ */
+fn append_history(track: &Track) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path()?)?;
+    writeln!(file, "{}", serde_json::to_string(track)?)?;
+    Ok(())
+}
+
+/**
+Read every track of the history file, oldest first. Unreadable lines are skipped
+ */
+pub fn read_history() -> Result<Vec<Track>, Error> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
@@ -0,0 +1,163 @@
+/// Start of the title in a metadata block
+const STREAM_TITLE: &str = "StreamTitle='";
+/// Field usually following the title
+const STREAM_URL: &str = "';StreamUrl='";
+
+/**
+Split a Shoutcast/Icecast stream into its audio and the metadata blocks sent every `icy-metaint` bytes
+ */
+#[derive(Default)]
+pub struct IcyParser {
+    /// Audio bytes between two metadata blocks, None if the server sends no metadata
+    interval: Option<usize>,
+    /// Audio bytes left before the next metadata block
+    audio: usize,
+    /// Length of the metadata block being read
+    length: usize,
+    metadata: Vec<u8>,
+}
+
+impl IcyParser {
+    /**
+    Interval announced by the `icy-metaint` header. The stream has no metadata if it is not a number
+     */
+    pub fn set_interval(&mut self, header: &str) {
+        let interval = header.trim().parse().unwrap_or(0);
+        self.interval = Some(interval).filter(|interval| *interval > 0);
+        self.audio = interval;
+    }
+
+    /**
+    Audio bytes of a downloaded chunk, and the last title found in it
+     */
+    pub fn parse(&mut self, mut data: &[u8]) -> (Vec<u8>, Option<String>) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return (data.to_vec(), None),
+        };
+        let mut audio = Vec::with_capacity(data.len());
+        let mut title = None;
+        while !data.is_empty() {
+            if self.audio > 0 {
+                let count = self.audio.min(data.len());
+                audio.extend_from_slice(&data[..count]);
+                data = &data[count..];
+                self.audio -= count;
+            } else if self.length == 0 {
+                // the first byte gives the length of the block, in 16 bytes units
+                self.length = data[0] as usize * 16;
+                data = &data[1..];
+                if self.length == 0 {
+                    self.audio = interval;
+                }
+            } else {
+                let count = (self.length - self.metadata.len()).min(data.len());
+                self.metadata.extend_from_slice(&data[..count]);
+                data = &data[count..];
+                if self.metadata.len() == self.length {
+                    title = stream_title(&self.metadata).or(title);
+                    self.metadata.clear();
+                    self.length = 0;
+                    self.audio = interval;
+                }
+            }
+        }
+        (audio, title)
+    }
+}
+
+/**
+Title of a metadata block like `StreamTitle='Artist - Song';StreamUrl='';`
+ */
+fn stream_title(block: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(block);
+    let text = text.trim_end_matches('\0');
+    let start = text.find(STREAM_TITLE)? + STREAM_TITLE.len();
+    // the title may hold quotes and semicolons itself
+    let end = text[start..]
+        .find(STREAM_URL)
+        .or_else(|| text[start..].find("';"))
+        .or_else(|| text[start..].rfind('\''))
+        .map_or(text.len(), |end| start + end);
+    let title = text[start..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+    Metadata block as sent in the stream: its length in 16 bytes units, then the text padded with zeros
+     */
+    fn block(text: &str) -> Vec<u8> {
+        let length = text.len().div_ceil(16);
+        let mut block = vec![length as u8];
+        block.extend_from_slice(text.as_bytes());
+        block.resize(1 + length * 16, 0);
+        block
+    }
+
+    fn parser(interval: &str) -> IcyParser {
+        let mut parser = IcyParser::default();
+        parser.set_interval(interval);
+        parser
+    }
+
+    #[test]
+    fn metadata_split_across_chunks() {
+        let stream = [
+            b"abcd".to_vec(),
+            block("StreamTitle='Armin van Buuren - Blah Blah Blah';StreamUrl='';"),
+            b"efgh".to_vec(),
+        ]
+        .concat();
+        for split in 0..=stream.len() {
+            let mut parser = parser("4");
+            let (mut audio, first) = parser.parse(&stream[..split]);
+            let (rest, second) = parser.parse(&stream[split..]);
+            audio.extend(rest);
+            assert_eq!(audio, b"abcdefgh", "split at {}", split);
+            assert_eq!(
+                first.or(second).as_deref(),
+                Some("Armin van Buuren - Blah Blah Blah")
+            );
+        }
+    }
+
+    #[test]
+    fn empty_metadata_block() {
+        let mut parser = parser("4");
+        let stream = [b"abcd".as_slice(), &[0], b"efgh", &[0], b"ij"].concat();
+        assert_eq!(parser.parse(&stream), (b"abcdefghij".to_vec(), None));
+    }
+
+    #[test]
+    fn without_interval_the_stream_is_audio() {
+        let stream = [b"abcd".to_vec(), block("StreamTitle='A - B';")].concat();
+        assert_eq!(IcyParser::default().parse(&stream), (stream.clone(), None));
+        for header in ["", "abc", "-16", "0"] {
+            assert_eq!(parser(header).parse(&stream), (stream.clone(), None));
+        }
+    }
+
+    #[test]
+    fn title_with_quotes_and_semicolons() {
+        let title = |block: &str| stream_title(block.as_bytes());
+        assert_eq!(
+            title("StreamTitle='Guns N' Roses - Don't Cry; Live';StreamUrl='';").as_deref(),
+            Some("Guns N' Roses - Don't Cry; Live")
+        );
+        assert_eq!(
+            title("StreamTitle='Artist - 'Quoted';Song';StreamUrl='http://x';").as_deref(),
+            Some("Artist - 'Quoted';Song")
+        );
+        // without StreamUrl, the title ends at the first closing quote
+        assert_eq!(
+            title("StreamTitle='Artist - Song';").as_deref(),
+            Some("Artist - Song")
+        );
+        assert_eq!(title("StreamTitle='';StreamUrl='';"), None);
+        assert_eq!(title("StreamUrl='';"), None);
+    }
+}
@@ -1,25 +1,63 @@
+mod alarm;
 mod api;
 mod app;
+mod backend;
 mod config;
+mod daemon;
+mod daemon_backend;
+mod headless;
+mod history;
+#[cfg(feature = "rodio_player")]
+mod icy;
 mod mpris;
+#[cfg(feature = "libmpv_player")]
+mod mpv_backend;
+mod navigation;
+mod notifier;
 mod player;
+mod recorder;
+#[cfg(feature = "rodio_player")]
+mod rodio_backend;
+#[cfg(feature = "rodio_player")]
+mod stream_buffer;
+mod timer;
 mod tools;
 mod ui;
 
-use crate::api::stations_list;
-use crate::mpris::{launch_mpris_server, Response};
-use crate::tools::pause;
+use crate::alarm::{next_alarm, parse_days, parse_time, wait_alarm, Alarm};
+use crate::api::{ApiClient, Quality, Station};
+use crate::backend::Backend;
+use crate::config::{
+    read_alarms, read_config, read_stations_cache, write_alarms, write_stations_cache,
+};
+use crate::daemon::{socket_path, Reply, Request};
+use crate::headless::Headless;
+use crate::history::read_history;
+use crate::navigation::NextPolicy;
+use crate::player::PlaybackState;
+use crate::recorder::{Recorder, TRACK_POLL};
+use crate::tools::{parse_duration, pause};
+use chrono::{Local, NaiveDate, NaiveTime};
+use clap::builder::{PossibleValue, PossibleValuesParser, TypedValueParser};
 use clap::{Parser, Subcommand};
+use color_eyre::eyre::eyre;
 use crossbeam::channel;
 use rand::random;
 use std::process::exit;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-      
+    /// Base url of the radio record api (overrides RRT_API_URL and the config file)
+    #[arg(long, global = true)]
+    api_url: Option<String>,
+
+    /// Audio backend (overrides the config file)
+    #[arg(long, global = true, value_enum)]
+    backend: Option<Backend>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -27,26 +65,198 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// List available stations
-    List{
+    List {
         #[arg(short, long, default_value_t = false)]
-        line: bool, 
+        line: bool,
     },
     /// Play the specified station (random if none)
-    Play{
+    Play {
+        #[arg(short, long)]
+        station: Option<String>,
+        /// Stream quality (overrides the config file)
+        #[arg(short, long, value_parser = quality_parser())]
+        quality: Option<Quality>,
+        /// Stop the playback after a duration (e.g. 90, 45m, 1h30m)
+        #[arg(long, value_parser = parse_duration)]
+        sleep: Option<Duration>,
+        /// Station played by Next (overrides the config file)
+        #[arg(long, value_enum)]
+        next: Option<NextPolicy>,
+    },
+    /// Run the player in the background, controlled through a local socket
+    Daemon {
+        /// Station to play at once
         #[arg(short, long)]
-        station:Option<String>,
+        station: Option<String>,
+        /// Stream quality (overrides the config file)
+        #[arg(short, long, value_parser = quality_parser())]
+        quality: Option<Quality>,
+        /// Station played by Next (overrides the config file)
+        #[arg(long, value_enum)]
+        next: Option<NextPolicy>,
+    },
+    /// Send a command to the running daemon
+    Ctl {
+        #[command(subcommand)]
+        request: Request,
     },
+    /// Record the specified station to disk for a duration (e.g. 90, 45m, 1h30m)
+    Record {
+        #[arg(short, long)]
+        station: String,
+        #[arg(short, long, value_parser = parse_duration)]
+        duration: Duration,
+        /// Stream quality (overrides the config file)
+        #[arg(short, long, value_parser = quality_parser())]
+        quality: Option<Quality>,
+    },
+    /// Show the tracks heard, the oldest first
+    History {
+        /// Only the tracks heard on this station
+        #[arg(short, long)]
+        station: Option<String>,
+        /// Only the tracks heard since this day (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+    },
+    /// Manage the alarms starting a station at a given time
+    Alarm {
+        #[command(subcommand)]
+        command: AlarmCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum AlarmCommands {
+    /// Add an alarm, ringing once if no days are given
+    Add {
+        #[arg(short, long)]
+        station: String,
+        /// Time of the alarm (HH:MM)
+        #[arg(short, long, value_parser = parse_time)]
+        time: NaiveTime,
+        /// Days of the alarm, e.g. mon,wed,fri, daily, weekdays or weekend
+        #[arg(short, long)]
+        days: Option<String>,
+    },
+    /// List the alarms
+    List,
+    /// Remove an alarm
+    Remove { id: u32 },
+    /// Wait for the next alarm and play its station
+    Wait,
+}
+
+/// Error of the players given an empty stations list
+const NO_STATION: &str = "No station to play, the stations list is empty";
+
+/// Names of the stream qualities on the command line, as in the config file
+const QUALITIES: [(&str, Quality); 4] = [
+    ("low", Quality::Low),
+    ("medium", Quality::Medium),
+    ("high", Quality::High),
+    ("hls", Quality::Hls),
+];
+
+/**
+Parser of the `--quality` option, mapping the names to the stream qualities
+ */
+fn quality_parser() -> impl TypedValueParser<Value = Quality> {
+    PossibleValuesParser::new(
+        QUALITIES.map(|(name, quality)| PossibleValue::new(name).help(quality.to_string())),
+    )
+    .map(|name| {
+        QUALITIES
+            .into_iter()
+            .find(|(known, _)| *known == name)
+            .unwrap()
+            .1
+    })
+}
+
+/**
+Fetch the stations list and keep it in cache. Use the cached list if the api is unreachable
+ */
+fn fetch_stations(api: &ApiClient) -> color_eyre::Result<Vec<Station>> {
+    match api.stations_list() {
+        Ok(list) => {
+            let _ = write_stations_cache(&list);
+            Ok(list)
+        }
+        Err(error) => {
+            let cached = read_stations_cache()?;
+            if cached.is_empty() {
+                return Err(error.into());
+            }
+            eprintln!("Using the cached stations list : {}", error);
+            Ok(cached)
+        }
+    }
+}
+
+/**
+Send a command to the running daemon and print its reply
+ */
+fn control(request: &Request) {
+    match daemon::request(request) {
+        Ok(Reply::Ok) => {}
+        Ok(Reply::Status {
+            station,
+            state,
+            title,
+            volume,
+            muted,
+        }) => {
+            match state {
+                PlaybackState::Error(error) => println!("{} : {}", station.title, error),
+                state => println!("{} : {}", state, station.title),
+            }
+            if let Some(title) = title {
+                println!("{}", title);
+            }
+            match muted {
+                true => println!("Volume : {} (muted)", volume),
+                false => println!("Volume : {}", volume),
+            }
+        }
+        Ok(Reply::NowPlaying { title }) => {
+            if let Some(title) = title {
+                println!("{}", title);
+            }
+        }
+        Ok(Reply::Error { message }) => {
+            eprintln!("{}", message);
+            exit(1);
+        }
+        Err(error) => {
+            eprintln!(
+                "Could not reach the daemon on {} : {}",
+                socket_path().display(),
+                error
+            );
+            exit(1);
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
     let cli = Cli::parse();
-    
+    let config = read_config()?;
+    let api = ApiClient::resolve(cli.api_url, config.api_url.clone());
+    let backend = cli.backend.unwrap_or(config.backend);
+
+    // the daemon is asked directly, without the stations list
+    if let Some(Commands::Ctl { request }) = &cli.command {
+        control(request);
+        return Ok(());
+    }
+
     if let Some(cmd) = cli.command {
-        let list = stations_list().unwrap();
+        let list = fetch_stations(&api)?;
         match cmd {
-           Commands::List{line} => {
+            Commands::List { line } => {
                 let mut s = String::new();
                 for station in list {
                     if line {
@@ -57,77 +267,206 @@ async fn main() -> color_eyre::Result<()> {
                 }
                 println!("{}", s);
             }
-            Commands::Play{station} => {
+            Commands::Play {
+                station,
+                quality,
+                sleep,
+                next,
+            } => {
                 // background player in cli
-
-                let mut player = player::Player::new(list[0].stream_320.clone());
-                let mut station_name = if let Some(station) = station {
+                let quality = quality.unwrap_or(config.quality);
+                let station = match station {
                     // if a station is selected play it
-                    if let Some(station_found) = list.iter().find(|s| s.prefix == station) {
-                        player.play(&station_found.stream_320);
-                        station_found.title.clone()
-                    } else {
+                    Some(station) => match list.iter().find(|s| s.prefix == station) {
+                        Some(station) => station.clone(),
+                        None => {
+                            eprintln!("Station not found");
+                            exit(1);
+                        }
+                    },
+                    // play random station
+                    None if list.is_empty() => return Err(eyre!(NO_STATION)),
+                    None => list[random::<usize>() % list.len()].clone(),
+                };
+
+                let mut headless =
+                    Headless::new(list, config, backend, quality, next, station.clone()).await?;
+                if let Some(sleep) = sleep {
+                    headless.set_sleep(sleep);
+                }
+                headless.tune(station);
+                thread::spawn(move || headless.run(channel::never()));
+                pause();
+            }
+            Commands::Daemon {
+                station,
+                quality,
+                next,
+            } => {
+                let listener = match daemon::bind() {
+                    Ok(listener) => listener,
+                    Err(error) => {
+                        eprintln!(
+                            "Could not listen on {} : {}",
+                            socket_path().display(),
+                            error
+                        );
+                        exit(1);
+                    }
+                };
+                let quality = quality.unwrap_or(config.quality);
+                let found = match &station {
+                    Some(station) => match list.iter().find(|s| s.prefix == *station) {
+                        Some(station) => Some(station.clone()),
+                        None => {
+                            eprintln!("Station not found");
+                            exit(1);
+                        }
+                    },
+                    None => None,
+                };
+
+                let first = match found.clone().or_else(|| list.first().cloned()) {
+                    Some(first) => first,
+                    None => return Err(eyre!(NO_STATION)),
+                };
+                let mut headless =
+                    Headless::new(list, config, backend, quality, next, first).await?;
+                if let Some(station) = found {
+                    headless.tune(station);
+                }
+                let (controls_tx, controls_rx) = channel::bounded(1);
+                thread::spawn(move || headless.run(controls_rx));
+                println!("Listening on {}", socket_path().display());
+                daemon::serve(listener, controls_tx);
+            }
+            Commands::Record {
+                station,
+                duration,
+                quality,
+            } => {
+                let quality = backend.playable(quality.unwrap_or(config.quality));
+                let station = match list.iter().find(|s| s.prefix == station) {
+                    Some(station) => station,
+                    None => {
                         eprintln!("Station not found");
                         exit(1);
                     }
-                } else {
-                    // play random station
-                    let random = random::<usize>() % list.len();
-                    let station = &list[random];
-                    player.play(&station.stream_320);
-                    station.title.clone()
                 };
+                let url = station.stream_url(quality);
 
-                println!("Now playing : {}", station_name);
-                // launch and handle mpris interface
-                let (mpris_tx, mpris_rx) = channel::bounded(1);
-                let (tx, rx) = channel::bounded(1);
+                let mut player = player::Player::new(url.to_string(), &config, backend);
+                player.play(url);
+                let mut recorder = Recorder::new(&config);
+                let path = recorder.start(
+                    &player,
+                    station,
+                    url,
+                    api.now_playing(station.id).ok().flatten(),
+                )?;
+                println!("Recording to {}", path.display());
 
-                let _conn = launch_mpris_server(mpris_tx, rx).await?;
-                
-                thread::spawn(move || loop {
-                    if mpris_rx.is_empty() {
-                        thread::sleep(Duration::from_millis(200));
-                        continue;
-                    }
-                    if let app::Event::Mpris(event) = mpris_rx.recv().unwrap() {
-                        match event {
-                        mpris::Command::PlayPause => player.toggle_play(),
-                        mpris::Command::Stop => player.stop(),
-                        mpris::Command::Play => player.resume(),
-                        mpris::Command::Next => {
-                            let random = random::<usize>() % list.len();
-                            let station = list[random].clone();
-                            station_name = station.title.clone();
-                            println!("Now playing : {}", station.title);
-                            player.force_play(&station.stream_320);
+                let start = Instant::now();
+                while start.elapsed() < duration {
+                    thread::sleep(TRACK_POLL.min(duration.saturating_sub(start.elapsed())));
+                    if let Ok(Some(title)) = api.now_playing(station.id) {
+                        if let Some(path) = recorder.track_changed(&player, url, title)? {
+                            println!("Recording to {}", path.display());
                         }
-                        mpris::Command::Previous => {}
-                        mpris::Command::NowPlaying => {
-                            #[cfg(feature = "libmpv_player")]
-                            {
-                                if let Some(title) = player.now_playing() {
-                                    tx.send(Response::NowPlaying{title,artist:station_name.clone()}).unwrap();
-                                }
-                            }
-                        }
-                        mpris::Command::Status => {
-                            let status = if player.is_playing() {
-                                "Playing"
-                            } else {
-                                "Stopped"
-                            };
-                            tx.send(Response::Status(status.to_string())).unwrap();
+                    }
+                }
+                recorder.stop(&player)?;
+                recorder.finish();
+            }
+            Commands::History { station, since } => {
+                let station = match station {
+                    Some(station) => match list.iter().find(|s| s.prefix == station) {
+                        Some(station) => Some(station.id),
+                        None => {
+                            eprintln!("Station not found");
+                            exit(1);
                         }
+                    },
+                    None => None,
+                };
+                for track in read_history()? {
+                    if station.is_some_and(|id| id != track.station) {
+                        continue;
                     }
-                    };
-                });
-                pause();
+                    if since.is_some_and(|since| track.start.date_naive() < since) {
+                        continue;
+                    }
+                    let title = list
+                        .iter()
+                        .find(|s| s.id == track.station)
+                        .map_or_else(|| format!("#{}", track.station), |s| s.title.clone());
+                    println!(
+                        "{} {} : {} - {}",
+                        track.start.format("%Y-%m-%d %H:%M"),
+                        title,
+                        track.artist,
+                        track.song
+                    );
+                }
             }
+            Commands::Alarm { command } => match command {
+                AlarmCommands::Add {
+                    station,
+                    time,
+                    days,
+                } => {
+                    if !list.iter().any(|s| s.prefix == station) {
+                        eprintln!("Station not found");
+                        exit(1);
+                    }
+                    let days = match days.as_deref().map(parse_days).transpose() {
+                        Ok(days) => days.unwrap_or_default(),
+                        Err(error) => {
+                            eprintln!("{}", error);
+                            exit(1);
+                        }
+                    };
+                    let mut alarms = read_alarms()?;
+                    let alarm = Alarm::new(&alarms, station, time, days);
+                    println!("Alarm added : {}", alarm);
+                    alarms.push(alarm);
+                    write_alarms(&alarms)?;
+                }
+                AlarmCommands::List => {
+                    let alarms = read_alarms()?;
+                    for alarm in &alarms {
+                        println!("{}", alarm);
+                    }
+                    if let Some((alarm, at)) = next_alarm(&alarms, Local::now()) {
+                        println!(
+                            "Next alarm : #{} at {}",
+                            alarm.id,
+                            at.format("%Y-%m-%d %H:%M")
+                        );
+                    }
+                }
+                AlarmCommands::Remove { id } => {
+                    let mut alarms = read_alarms()?;
+                    let count = alarms.len();
+                    alarms.retain(|alarm| alarm.id != id);
+                    if alarms.len() == count {
+                        eprintln!("Alarm not found");
+                        exit(1);
+                    }
+                    write_alarms(&alarms)?;
+                }
+                AlarmCommands::Wait => wait_alarm(&list, &config, backend)?,
+            },
+            // already sent
+            Commands::Ctl { .. } => {}
         }
         Ok(())
     } else {
-        // launch the tui app
-        app::App::new().start().await
+        // launch the tui app, controlling the running daemon if there is one
+        let backend = match daemon::is_running() {
+            true => Backend::Daemon,
+            false => backend,
+        };
+        app::App::new(api, config, backend).start().await
     }
 }
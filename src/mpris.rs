@@ -1,34 +1,189 @@
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::Sender;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use zbus::zvariant::Value;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+use tokio::sync::watch;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Value};
 use zbus::{interface, Connection, ConnectionBuilder};
 
+use crate::api::{Genre, Station, Title};
 use crate::app::Event;
+use crate::tools::icon_path;
 
 pub enum Command {
     PlayPause,
     Play,
+    Pause,
     Stop,
     Next,
     Previous,
-    NowPlaying,
-    Status,
+    SetVolume(f64),
+    Quit,
+    /// Object path of the playlist to play
+    ActivatePlaylist(String),
 }
 
-pub enum Response {
-    NowPlaying { title: String, artist: String },
-    Status(String),
+/**
+State of the player as seen by MPRIS clients, published by the app after every change.
+The D-Bus handlers only read the last one, they never wait for the app
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Snapshot {
+    /// Title of the track, or the state of the stream while nothing is heard
+    pub title: String,
+    /// Artist and song of the track, when they are known
+    pub track: Option<Title>,
+    pub station: Option<Station>,
+    pub status: &'static str,
+    /// Between 0.0 and 1.0
+    pub volume: f64,
+    /// End of the sleep timer
+    pub sleep: Option<Instant>,
+    pub playlists: Vec<Playlist>,
+    pub active_playlist: Option<Playlist>,
+}
+
+/**
+List of stations offered to MPRIS clients: the favorites or the stations of a genre
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Playlist {
+    Favorites,
+    Genre(Genre),
+}
+
+impl Playlist {
+    /**
+    Playlists of the stations, the favorites first if there are some
+     */
+    pub fn list(favorites: &[Station], genres: Vec<Genre>) -> Vec<Playlist> {
+        let favorites = (!favorites.is_empty()).then_some(Playlist::Favorites);
+        favorites
+            .into_iter()
+            .chain(genres.into_iter().map(Playlist::Genre))
+            .collect()
+    }
+
+    pub fn path(&self) -> String {
+        match self {
+            Playlist::Favorites => format!("{}/rrt/playlist/favorites", PATH),
+            Playlist::Genre(genre) => format!("{}/rrt/playlist/genre_{}", PATH, genre.id),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Playlist::Favorites => "Favorites".to_string(),
+            Playlist::Genre(genre) => genre.name.clone(),
+        }
+    }
+
+    /**
+    Stations of the playlist, the stations list giving the genres
+     */
+    pub fn stations(&self, list: &[Station], favorites: &[Station]) -> Vec<Station> {
+        match self {
+            Playlist::Favorites => favorites.to_vec(),
+            Playlist::Genre(genre) => list
+                .iter()
+                .filter(|station| station.genre.contains(genre))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Playlist as sent over D-Bus: path, name and icon
+    fn to_dbus(&self) -> (OwnedObjectPath, String, String) {
+        (
+            OwnedObjectPath::try_from(self.path()).unwrap(),
+            self.name(),
+            String::new(),
+        )
+    }
 }
 
 pub struct MediaPlayerInterface {
     pub tx: Sender<Event>,
-    pub rx: Receiver<Response>,
+    pub state: watch::Receiver<Snapshot>,
+}
+
+pub struct RootInterface {
+    pub tx: Sender<Event>,
+}
+
+pub struct PlaylistsInterface {
+    pub tx: Sender<Event>,
+    pub state: watch::Receiver<Snapshot>,
+}
+
+/**
+MPRIS server running on the session bus
+ */
+pub struct MprisServer {
+    _conn: Connection,
+    state: watch::Sender<Snapshot>,
+}
+
+impl MprisServer {
+    /**
+    Publish the state of the player. The clients are signaled in the background of the properties which changed
+     */
+    pub fn publish(&self, snapshot: Snapshot) {
+        self.state.send_if_modified(|state| {
+            if *state == snapshot {
+                return false;
+            }
+            *state = snapshot;
+            true
+        });
+    }
 }
 
 #[cfg(debug_assertions)]
 const INAME: &str = "org.mpris.MediaPlayer2.rrt_test";
 #[cfg(not(debug_assertions))]
 const INAME: &str = "org.mpris.MediaPlayer2.rrt";
+const PATH: &str = "/org/mpris/MediaPlayer2";
+
+#[allow(non_snake_case)]
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl RootInterface {
+    #[zbus(property, name = "CanQuit")]
+    fn CanQuit(&self) -> bool {
+        true
+    }
+    #[zbus(property, name = "CanRaise")]
+    fn CanRaise(&self) -> bool {
+        false
+    }
+    #[zbus(property, name = "HasTrackList")]
+    fn HasTrackList(&self) -> bool {
+        false
+    }
+    #[zbus(property, name = "Identity")]
+    fn Identity(&self) -> &str {
+        "Radio Record"
+    }
+    #[zbus(property, name = "DesktopEntry")]
+    fn DesktopEntry(&self) -> &str {
+        "rrt"
+    }
+    #[zbus(property, name = "SupportedUriSchemes")]
+    fn SupportedUriSchemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+    #[zbus(property, name = "SupportedMimeTypes")]
+    fn SupportedMimeTypes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // the terminal can not be raised
+    async fn Raise(&self) {}
+    async fn Quit(&self) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::Quit)
+    }
+}
 
 #[allow(non_snake_case)]
 #[interface(name = "org.mpris.MediaPlayer2.Player")]
@@ -41,6 +196,10 @@ impl MediaPlayerInterface {
     fn CanPlay(&self) -> bool {
         true
     }
+    #[zbus(property, name = "CanPause")]
+    fn CanPause(&self) -> bool {
+        true
+    }
     #[zbus(property, name = "CanStop")]
     fn CanStop(&self) -> bool {
         true
@@ -54,69 +213,506 @@ impl MediaPlayerInterface {
         true
     }
     #[zbus(property, name = "Metadata")]
-    async fn Metadata(&self) -> HashMap<&str, Value> {
-        self.tx
-            .send(Event::Mpris(Command::NowPlaying))
-            .expect("Could not send");
+    async fn Metadata(&self) -> HashMap<&str, Value<'_>> {
+        let state = self.state.borrow();
         let mut map = HashMap::new();
-        if let Response::NowPlaying{title, artist} = self.rx.recv().unwrap() {
-            map.insert("xesam:title", Value::from(title));
-            map.insert("xesam:artist", Value::from(artist));
-            return map;
+        let station = match &state.station {
+            Some(station) => station,
+            None => return map,
+        };
+        if let Ok(path) = ObjectPath::try_from(track_id(station, &state.title)) {
+            map.insert("mpris:trackid", Value::from(path));
+        }
+        match &state.track {
+            Some(track) => {
+                map.insert("xesam:title", Value::from(track.song.clone()));
+                map.insert("xesam:artist", Value::from(vec![track.artist.clone()]));
+            }
+            None => {
+                map.insert("xesam:title", Value::from(state.title.clone()));
+                map.insert("xesam:artist", Value::from(vec![station.title.clone()]));
+            }
+        }
+        if let Some(icon) = icon_path(station) {
+            map.insert(
+                "mpris:artUrl",
+                Value::from(format!("file://{}", icon.display())),
+            );
+        }
+        map.insert("xesam:album", Value::from(station.title.clone()));
+        // remaining time before the playback stops, in microseconds like mpris:length
+        if let Some(sleep) = state.sleep {
+            let remaining = sleep.saturating_duration_since(Instant::now());
+            map.insert(
+                "rrt:sleepRemaining",
+                Value::from(remaining.as_micros() as i64),
+            );
         }
         map
     }
     #[zbus(property, name = "PlaybackStatus")]
     async fn PlaybackStatus(&self) -> String {
-        self.tx
-            .send(Event::Mpris(Command::Status))
-            .expect("Could not send");
-        if let Ok(Response::Status(status)) = self.rx.recv() {
-            status
-        } else {
-            "Unknown".to_string()
-        }
+        self.state.borrow().status.to_string()
+    }
+
+    #[zbus(property, name = "Volume")]
+    async fn Volume(&self) -> f64 {
+        self.state.borrow().volume
+    }
+    #[zbus(property, name = "Volume")]
+    async fn set_Volume(&mut self, volume: f64) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::SetVolume(volume))
     }
 
     // Can be `async` as well.
-    async fn Next(&mut self) {
-        self.tx
-            .send(Event::Mpris(Command::Next))
-            .expect("Could not send")
+    async fn Next(&mut self) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::Next)
     }
-    async fn Previous(&mut self) {
-        self.tx
-            .send(Event::Mpris(Command::Previous))
-            .expect("Could not send")
+    async fn Previous(&mut self) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::Previous)
     }
 
-    async fn Play(&mut self) {
-        self.tx
-            .send(Event::Mpris(Command::Play))
-            .expect("Could not send")
+    async fn Play(&mut self) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::Play)
     }
-    async fn Stop(&mut self) {
-        self.tx
-            .send(Event::Mpris(Command::Stop))
-            .expect("Could not send");
+    async fn Pause(&mut self) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::Pause)
     }
-    async fn PlayPause(&mut self) {
-        self.tx
-            .send(Event::Mpris(Command::PlayPause))
-            .expect("Could not send");
+    async fn Stop(&mut self) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::Stop)
+    }
+    async fn PlayPause(&mut self) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::PlayPause)
     }
 }
 
-pub async fn launch_mpris_server(
+#[allow(non_snake_case)]
+#[interface(name = "org.mpris.MediaPlayer2.Playlists")]
+impl PlaylistsInterface {
+    #[zbus(property, name = "PlaylistCount")]
+    async fn PlaylistCount(&self) -> u32 {
+        self.state.borrow().playlists.len() as u32
+    }
+    #[zbus(property, name = "Orderings")]
+    fn Orderings(&self) -> Vec<&str> {
+        vec!["UserDefined", "Alphabetical"]
+    }
+    #[zbus(property, name = "ActivePlaylist")]
+    async fn ActivePlaylist(&self) -> (bool, (OwnedObjectPath, String, String)) {
+        match &self.state.borrow().active_playlist {
+            Some(playlist) => (true, playlist.to_dbus()),
+            None => (
+                false,
+                (
+                    OwnedObjectPath::try_from("/").unwrap(),
+                    String::new(),
+                    String::new(),
+                ),
+            ),
+        }
+    }
+
+    async fn ActivatePlaylist(&mut self, playlist_id: ObjectPath<'_>) -> zbus::fdo::Result<()> {
+        send(&self.tx, Command::ActivatePlaylist(playlist_id.to_string()))
+    }
+    async fn GetPlaylists(
+        &self,
+        index: u32,
+        max_count: u32,
+        order: &str,
+        reverse_order: bool,
+    ) -> Vec<(OwnedObjectPath, String, String)> {
+        let mut playlists = self.state.borrow().playlists.clone();
+        if order == "Alphabetical" {
+            playlists.sort_by_key(|playlist| playlist.name());
+        }
+        if reverse_order {
+            playlists.reverse();
+        }
+        playlists
+            .iter()
+            .skip(index as usize)
+            .take(max_count as usize)
+            .map(Playlist::to_dbus)
+            .collect()
+    }
+}
+
+pub async fn launch_mpris_server(tx: Sender<Event>) -> color_eyre::Result<MprisServer> {
+    serve_mpris(ConnectionBuilder::session()?.name(INAME)?, tx).await
+}
+
+/**
+Serve the MPRIS interfaces on the connection being built
+ */
+async fn serve_mpris(
+    builder: ConnectionBuilder<'_>,
     tx: Sender<Event>,
-    rx: Receiver<Response>,
-) -> color_eyre::Result<Connection> {
-    let player = MediaPlayerInterface { tx, rx };
-    let conn = ConnectionBuilder::session()?
-        .name(INAME)?
-        .serve_at("/org/mpris/MediaPlayer2", player)?
+) -> color_eyre::Result<MprisServer> {
+    let (state, receiver) = watch::channel(Snapshot::default());
+    let root = RootInterface { tx: tx.clone() };
+    let playlists = PlaylistsInterface {
+        tx: tx.clone(),
+        state: receiver.clone(),
+    };
+    let player = MediaPlayerInterface {
+        tx,
+        state: receiver.clone(),
+    };
+    let conn = builder
+        .serve_at(PATH, root)?
+        .serve_at(PATH, player)?
+        .serve_at(PATH, playlists)?
         .build()
         .await?;
 
-    Ok(conn)
+    tokio::spawn(signal_changes(conn.clone(), receiver));
+
+    Ok(MprisServer { _conn: conn, state })
+}
+
+/**
+Emit PropertiesChanged for the properties which differ between two snapshots
+ */
+async fn signal_changes(conn: Connection, mut receiver: watch::Receiver<Snapshot>) {
+    let mut previous = receiver.borrow().clone();
+    while receiver.changed().await.is_ok() {
+        let current = receiver.borrow_and_update().clone();
+        if let Ok(iface_ref) = conn
+            .object_server()
+            .interface::<_, MediaPlayerInterface>(PATH)
+            .await
+        {
+            let iface = iface_ref.get().await;
+            let ctxt = iface_ref.signal_context();
+            if current.title != previous.title
+                || current.station != previous.station
+                || current.sleep != previous.sleep
+            {
+                let _ = iface.metadata_changed(ctxt).await;
+            }
+            if current.status != previous.status {
+                let _ = iface.playback_status_changed(ctxt).await;
+            }
+            if current.volume != previous.volume {
+                let _ = iface.volume_changed(ctxt).await;
+            }
+        }
+        if let Ok(iface_ref) = conn
+            .object_server()
+            .interface::<_, PlaylistsInterface>(PATH)
+            .await
+        {
+            let iface = iface_ref.get().await;
+            let ctxt = iface_ref.signal_context();
+            if current.playlists.len() != previous.playlists.len() {
+                let _ = iface.playlist_count_changed(ctxt).await;
+            }
+            if current.active_playlist != previous.active_playlist {
+                let _ = iface.active_playlist_changed(ctxt).await;
+            }
+        }
+        previous = current;
+    }
+}
+
+/**
+Id of the track for MPRIS clients, it changes with the station and the title
+ */
+fn track_id(station: &Station, title: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    title.hash(&mut hasher);
+    format!(
+        "/org/mpris/MediaPlayer2/rrt/track/{}_{:x}",
+        station.id,
+        hasher.finish()
+    )
+}
+
+/**
+Pass a command to the app without waiting for it. The client gets an error when the app is gone
+ */
+fn send(tx: &Sender<Event>, command: Command) -> zbus::fdo::Result<()> {
+    tx.try_send(Event::Mpris(command))
+        .map_err(|error| zbus::fdo::Error::Failed(error.to_string()))
+}
+
+/**
+Convert a MPRIS volume (0.0 to 1.0) to the player one (0 to 100)
+ */
+pub fn player_volume(volume: f64) -> u8 {
+    (volume.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crossbeam::channel::{self, Receiver};
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command as Process, Stdio};
+    use std::time::Duration;
+    use tokio::time::timeout;
+    use zbus::export::futures_util::StreamExt;
+    use zbus::fdo::{PropertiesChangedStream, PropertiesProxy};
+    use zbus::zvariant::OwnedValue;
+    use zbus::CacheProperties;
+
+    /**
+    Private bus for a test, its daemon stops with it
+     */
+    pub(crate) struct TestBus {
+        daemon: Child,
+        pub(crate) address: String,
+    }
+
+    impl TestBus {
+        pub(crate) fn start() -> Self {
+            let mut daemon = Process::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("dbus-daemon is needed to test the D-Bus services");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            Self {
+                daemon,
+                address: address.trim().to_string(),
+            }
+        }
+
+        pub(crate) async fn connect(&self) -> Connection {
+            ConnectionBuilder::address(self.address.as_str())
+                .unwrap()
+                .build()
+                .await
+                .unwrap()
+        }
+
+        /**
+        MPRIS server on the bus, with the events it sends to the app
+         */
+        async fn serve(&self) -> (MprisServer, Receiver<Event>) {
+            let (tx, rx) = channel::unbounded();
+            let builder = ConnectionBuilder::address(self.address.as_str())
+                .unwrap()
+                .name(INAME)
+                .unwrap();
+            (serve_mpris(builder, tx).await.unwrap(), rx)
+        }
+    }
+
+    impl Drop for TestBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[zbus::proxy(
+        interface = "org.mpris.MediaPlayer2.Player",
+        default_path = "/org/mpris/MediaPlayer2",
+        gen_blocking = false
+    )]
+    trait Player {
+        fn play_pause(&self) -> zbus::Result<()>;
+        fn next(&self) -> zbus::Result<()>;
+        #[zbus(property)]
+        fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+        #[zbus(property)]
+        fn playback_status(&self) -> zbus::Result<String>;
+        #[zbus(property)]
+        fn volume(&self) -> zbus::Result<f64>;
+    }
+
+    /**
+    Client of the player interface, every property is read from the server
+     */
+    async fn player(conn: &Connection) -> PlayerProxy<'static> {
+        PlayerProxy::builder(conn)
+            .destination(INAME)
+            .unwrap()
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+            .unwrap()
+    }
+
+    fn track() -> Title {
+        Title {
+            song: "Blah Blah Blah".to_string(),
+            artist: "Armin van Buuren".to_string(),
+        }
+    }
+
+    fn station() -> Station {
+        let mut station = Station::default();
+        station.id = 15016;
+        station.title = "Record".to_string();
+        station.prefix = "rr_main".to_string();
+        station
+    }
+
+    /**
+    Properties of the player interface sent in the next PropertiesChanged signal, with their value
+     */
+    async fn changed(stream: &mut PropertiesChangedStream<'_>) -> HashMap<String, OwnedValue> {
+        loop {
+            let signal = timeout(Duration::from_secs(5), stream.next())
+                .await
+                .expect("no PropertiesChanged signal")
+                .unwrap();
+            let args = signal.args().unwrap();
+            if args.interface_name() == "org.mpris.MediaPlayer2.Player" {
+                return args
+                    .changed_properties()
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.try_to_owned().unwrap()))
+                    .collect();
+            }
+        }
+    }
+
+    fn text(value: &OwnedValue) -> String {
+        String::try_from(value.try_clone().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn play_pause_next_and_metadata() {
+        let bus = TestBus::start();
+        let (server, events) = bus.serve().await;
+        server.publish(Snapshot {
+            title: track().to_string(),
+            track: Some(track()),
+            station: Some(station()),
+            status: "Playing",
+            volume: 0.5,
+            ..Snapshot::default()
+        });
+        let client = bus.connect().await;
+        let player = player(&client).await;
+
+        player.play_pause().await.unwrap();
+        assert!(matches!(
+            events.try_recv(),
+            Ok(Event::Mpris(Command::PlayPause))
+        ));
+        player.next().await.unwrap();
+        assert!(matches!(events.try_recv(), Ok(Event::Mpris(Command::Next))));
+
+        let metadata = player.metadata().await.unwrap();
+        assert_eq!(text(&metadata["xesam:title"]), "Blah Blah Blah");
+        assert_eq!(text(&metadata["xesam:album"]), "Record");
+        let artist = metadata["xesam:artist"].try_clone().unwrap();
+        assert_eq!(
+            Vec::<String>::try_from(artist).unwrap(),
+            ["Armin van Buuren"]
+        );
+        assert!(metadata.contains_key("mpris:trackid"));
+
+        // without track, the title is shown as sent by the station
+        server.publish(Snapshot {
+            title: "Connecting".to_string(),
+            station: Some(station()),
+            status: "Stopped",
+            ..Snapshot::default()
+        });
+        let metadata = player.metadata().await.unwrap();
+        assert_eq!(text(&metadata["xesam:title"]), "Connecting");
+        let artist = metadata["xesam:artist"].try_clone().unwrap();
+        assert_eq!(Vec::<String>::try_from(artist).unwrap(), ["Record"]);
+    }
+
+    #[tokio::test]
+    async fn commands_fail_once_the_app_is_gone() {
+        let bus = TestBus::start();
+        let (_server, events) = bus.serve().await;
+        let client = bus.connect().await;
+        let player = player(&client).await;
+        drop(events);
+        assert!(matches!(
+            player.play_pause().await,
+            Err(zbus::Error::MethodError(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn properties_are_read_from_the_snapshot() {
+        let bus = TestBus::start();
+        let (server, events) = bus.serve().await;
+        let client = bus.connect().await;
+        let player = player(&client).await;
+
+        let playing = Snapshot {
+            title: track().to_string(),
+            track: Some(track()),
+            station: Some(station()),
+            status: "Playing",
+            volume: 0.5,
+            ..Snapshot::default()
+        };
+        server.publish(playing.clone());
+        assert_eq!(player.playback_status().await.unwrap(), "Playing");
+        assert_eq!(player.volume().await.unwrap(), 0.5);
+
+        server.publish(Snapshot {
+            status: "Paused",
+            volume: 0.2,
+            ..playing
+        });
+        assert_eq!(player.playback_status().await.unwrap(), "Paused");
+        assert_eq!(player.volume().await.unwrap(), 0.2);
+        // the app is never asked
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn properties_changed_after_a_new_state() {
+        let bus = TestBus::start();
+        let (server, _events) = bus.serve().await;
+        let client = bus.connect().await;
+        let properties = PropertiesProxy::builder(&client)
+            .destination(INAME)
+            .unwrap()
+            .path(PATH)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let mut signals = properties.receive_properties_changed().await.unwrap();
+
+        let playing = Snapshot {
+            title: track().to_string(),
+            track: Some(track()),
+            station: Some(station()),
+            status: "Playing",
+            volume: 0.5,
+            ..Snapshot::default()
+        };
+        server.publish(playing.clone());
+        let metadata = changed(&mut signals).await;
+        assert!(metadata.contains_key("Metadata"));
+        let status = changed(&mut signals).await;
+        assert_eq!(text(&status["PlaybackStatus"]), "Playing");
+        let volume = changed(&mut signals).await;
+        assert_eq!(
+            f64::try_from(volume["Volume"].try_clone().unwrap()).unwrap(),
+            0.5
+        );
+
+        // the same state again changes nothing, only the volume is signaled
+        server.publish(playing.clone());
+        server.publish(Snapshot {
+            volume: 0.2,
+            ..playing
+        });
+        let volume = changed(&mut signals).await;
+        assert_eq!(volume.keys().collect::<Vec<_>>(), ["Volume"]);
+        assert_eq!(
+            f64::try_from(volume["Volume"].try_clone().unwrap()).unwrap(),
+            0.2
+        );
+    }
 }
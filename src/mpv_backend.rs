@@ -0,0 +1,260 @@
+use crate::backend::{AudioBackend, SharedState, RECORD_CLOSE_GRACE};
+use crate::config::Config;
+use crate::player::{PlaybackState, PlayerEvent};
+use libmpv::{
+    events::{Event, EventContext, PropertyData},
+    mpv_end_file_reason, mpv_error, FileState, Format, Mpv,
+};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest delay between two reconnection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/**
+What mpv was asked to play, shared with the event thread
+ */
+#[derive(Default)]
+struct MpvStream {
+    /// url that should be playing, None when stopped
+    url: Option<String>,
+    /// the current url was opened at least once
+    loaded: bool,
+    paused: bool,
+    attempt: u32,
+    retry_at: Option<Instant>,
+    /// why the stream was dropped, told when giving up
+    error: Option<String>,
+}
+
+impl MpvStream {
+    /**
+    The stream ended without being stopped or replaced, reconnect after a delay.
+    A stream which never played is not reconnected
+     */
+    fn dropped(&mut self, error: Option<String>, state: &SharedState) {
+        if !self.loaded {
+            let failed = "Could not open the stream".to_string();
+            state.set(PlaybackState::Error(match error {
+                Some(error) => format!("{} : {}", failed, error),
+                None => failed,
+            }));
+            self.url = None;
+        } else if self.retry_at.is_none() {
+            self.error = error;
+            self.retry_at = Some(Instant::now() + backoff(self.attempt + 1));
+        }
+    }
+}
+
+/**
+Backend playing the streams with libmpv, a dropped stream is reconnected
+ */
+pub struct MpvBackend {
+    mpv: Arc<Mpv>,
+    stream: Arc<Mutex<MpvStream>>,
+    state: SharedState,
+}
+
+impl MpvBackend {
+    pub fn new(state: SharedState, config: &Config) -> Self {
+        let mpv = Arc::new(Mpv::new().unwrap());
+        mpv.set_property("volume", config.volume.min(100) as i64)
+            .unwrap();
+        mpv.set_property("vo", "null").unwrap();
+        let stream = Arc::new(Mutex::new(MpvStream::default()));
+
+        // events and property changes sent by mpv
+        let mpv_ = mpv.clone();
+        let stream_ = stream.clone();
+        let state_ = state.clone();
+        let max_attempts = config.reconnect_attempts;
+        thread::spawn(move || {
+            let (mpv, stream, state) = (mpv_, stream_, state_);
+            let mut events = EventContext::new(mpv.ctx);
+            events.disable_deprecated_events().unwrap();
+            for (id, (name, format)) in [
+                ("media-title", Format::String),
+                ("pause", Format::Flag),
+                ("volume", Format::Double),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                events.observe_property(name, format, id as u64).unwrap();
+            }
+
+            loop {
+                // sleep until the next event, or the next reconnection attempt
+                let timeout = match stream.lock().unwrap().retry_at {
+                    Some(at) => at.saturating_duration_since(Instant::now()).as_secs_f64(),
+                    None => -1.0,
+                };
+                let event = events.wait_event(timeout);
+
+                let mut stream = stream.lock().unwrap();
+                match event {
+                    Some(Ok(Event::StartFile)) if stream.url.is_some() && stream.attempt == 0 => {
+                        state.set(PlaybackState::Buffering);
+                    }
+                    Some(Ok(Event::FileLoaded)) | Some(Ok(Event::PlaybackRestart))
+                        if stream.url.is_some() =>
+                    {
+                        stream.attempt = 0;
+                        stream.loaded = true;
+                        state.set(match stream.paused {
+                            true => PlaybackState::Paused,
+                            false => PlaybackState::Playing,
+                        });
+                    }
+                    Some(Ok(Event::EndFile(reason))) if stream.url.is_some() => match reason {
+                        mpv_end_file_reason::Eof => stream.dropped(None, &state),
+                        mpv_end_file_reason::Error => {
+                            stream.dropped(Some("Playback error".to_string()), &state)
+                        }
+                        // stopped, or replaced by another url
+                        _ => {}
+                    },
+                    // a file ending on an error is reported as an error with its code
+                    Some(Err(error)) if stream.url.is_some() => {
+                        stream.dropped(Some(error_text(&error)), &state)
+                    }
+                    Some(Ok(Event::PropertyChange { name, change, .. })) => match (name, change) {
+                        ("media-title", PropertyData::Str(title)) => {
+                            state.send(PlayerEvent::Title(title.to_string()))
+                        }
+                        ("pause", PropertyData::Flag(paused)) => {
+                            stream.paused = paused;
+                            if stream.url.is_some() && stream.loaded {
+                                state.set(match paused {
+                                    true => PlaybackState::Paused,
+                                    false => PlaybackState::Playing,
+                                });
+                            }
+                        }
+                        ("volume", PropertyData::Double(volume)) => {
+                            state.send(PlayerEvent::Volume(volume.round() as u8))
+                        }
+                        _ => {}
+                    },
+                    Some(Ok(Event::Shutdown)) => break,
+                    _ => {}
+                }
+
+                if let (Some(at), Some(url)) = (stream.retry_at, stream.url.clone()) {
+                    if Instant::now() >= at {
+                        stream.retry_at = None;
+                        stream.attempt += 1;
+                        if stream.attempt > max_attempts {
+                            let lost = format!("Stream lost after {} attempts", max_attempts);
+                            state.set(PlaybackState::Error(match stream.error.take() {
+                                Some(error) => format!("{} : {}", lost, error),
+                                None => lost,
+                            }));
+                            stream.url = None;
+                            continue;
+                        }
+                        state.set(PlaybackState::Reconnecting {
+                            attempt: stream.attempt,
+                            max: max_attempts,
+                        });
+                        let _ = mpv.playlist_load_files(&[(&url, FileState::Replace, None)]);
+                    }
+                }
+            }
+        });
+
+        Self { mpv, stream, state }
+    }
+}
+
+impl AudioBackend for MpvBackend {
+    fn play(&mut self, url: &str) {
+        {
+            let mut stream = self.stream.lock().unwrap();
+            *stream = MpvStream {
+                url: Some(url.to_string()),
+                ..MpvStream::default()
+            };
+        }
+        self.state.set(PlaybackState::Buffering);
+        self.mpv
+            .playlist_load_files(&[(url, FileState::Replace, None)])
+            .unwrap();
+        self.mpv.unpause().unwrap();
+    }
+
+    fn stop(&mut self) {
+        {
+            let mut stream = self.stream.lock().unwrap();
+            stream.url = None;
+            stream.retry_at = None;
+        }
+        self.state.set(PlaybackState::Stopped);
+        self.mpv.playlist_clear().unwrap();
+        self.mpv.pause().unwrap();
+    }
+
+    // the state follows the pause property
+    fn pause(&mut self) {
+        let _ = self.mpv.pause();
+    }
+
+    fn resume(&mut self) {
+        let _ = self.mpv.unpause();
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        let _ = self.mpv.set_property("volume", volume as i64);
+    }
+
+    fn set_mute(&mut self, muted: bool) {
+        let _ = self.mpv.set_property("mute", muted);
+    }
+
+    fn record(&self, path: Option<PathBuf>) -> io::Result<()> {
+        let value = path
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.mpv
+            .set_property("stream-record", value.as_str())
+            .map_err(|error| io::Error::other(error.to_string()))
+    }
+
+    // mpv closes the previous file in its demuxer thread
+    fn record_close_delay(&self) -> Duration {
+        RECORD_CLOSE_GRACE
+    }
+
+    fn now_playing(&self) -> Option<String> {
+        self.mpv.get_property::<String>("media-title").ok()
+    }
+}
+
+/**
+Text of an error reported by mpv
+ */
+fn error_text(error: &libmpv::Error) -> String {
+    let text = match error {
+        libmpv::Error::Raw(code) => match *code {
+            mpv_error::LoadingFailed => "Loading failed",
+            mpv_error::AoInitFailed => "Audio output initialization failed",
+            mpv_error::NothingToPlay => "No audio data played",
+            mpv_error::UnknownFormat => "Unrecognized file format",
+            mpv_error::Unsupported => "Operation not supported",
+            _ => "Playback error",
+        },
+        _ => "Playback error",
+    };
+    text.to_string()
+}
+
+/**
+Delay before a reconnection attempt, doubling each time
+ */
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.saturating_sub(1).min(5)).min(MAX_BACKOFF)
+}
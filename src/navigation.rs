@@ -0,0 +1,251 @@
+use crate::api::Station;
+use crate::mpris::Playlist;
+use clap::ValueEnum;
+use rand::random;
+use serde::{Deserialize, Serialize};
+
+/// Stations kept to go back to with Previous
+const HISTORY_SIZE: usize = 100;
+
+/**
+Station played by Next in headless mode, chosen with the `next` config key or the `--next` option
+ */
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NextPolicy {
+    /// Any other station
+    #[default]
+    Random,
+    /// The next favorite
+    Favorites,
+    /// The next station sharing a genre with the playing one
+    Genre,
+}
+
+/**
+Previous and Next of the headless player. A playlist activated by a MPRIS client is stepped through,
+otherwise Next follows the policy. Previous goes back to the stations played before
+ */
+pub struct Navigation {
+    policy: NextPolicy,
+    /// Stations left, the last one first
+    history: Vec<Station>,
+    /// Playlist chosen by a MPRIS client, with its stations
+    playlist: Option<(Playlist, Vec<Station>)>,
+}
+
+impl Navigation {
+    pub fn new(policy: NextPolicy) -> Self {
+        Self {
+            policy,
+            history: Vec::new(),
+            playlist: None,
+        }
+    }
+
+    pub fn playlist(&self) -> Option<&Playlist> {
+        self.playlist.as_ref().map(|(playlist, _)| playlist)
+    }
+
+    /**
+    Station to play after the playing one, which is remembered for Previous
+     */
+    pub fn next(&mut self, list: &[Station], favorites: &[Station], playing: &Station) -> Station {
+        let next = match &self.playlist {
+            Some((_, stations)) => step_playlist(stations, playing, true),
+            None => match self.policy {
+                NextPolicy::Favorites if !favorites.is_empty() => {
+                    step_playlist(favorites, playing, true)
+                }
+                NextPolicy::Genre => {
+                    // the playing station is kept to find its place among the others
+                    let same_genre: Vec<Station> = list
+                        .iter()
+                        .filter(|station| {
+                            *station == playing
+                                || station.genre.iter().any(|g| playing.genre.contains(g))
+                        })
+                        .cloned()
+                        .collect();
+                    match same_genre.len() {
+                        0 | 1 => random_station(list, playing),
+                        _ => step_playlist(&same_genre, playing, true),
+                    }
+                }
+                _ => random_station(list, playing),
+            },
+        };
+        self.leave(playing);
+        next
+    }
+
+    /**
+    Station played before the playing one. Without history, the one before in the playlist
+     */
+    pub fn previous(&mut self, playing: &Station) -> Option<Station> {
+        self.history.pop().or_else(|| {
+            self.playlist
+                .as_ref()
+                .map(|(_, stations)| step_playlist(stations, playing, false))
+        })
+    }
+
+    /**
+    Step through a playlist from now on, its first station is the one to play
+     */
+    pub fn activate(
+        &mut self,
+        playlist: Playlist,
+        stations: Vec<Station>,
+        playing: &Station,
+    ) -> Option<Station> {
+        let first = stations.first().cloned();
+        self.playlist = Some((playlist, stations));
+        if first.is_some() {
+            self.leave(playing);
+        }
+        first
+    }
+
+    /**
+    Remember the playing station for Previous, another one is about to be played
+     */
+    pub fn leave(&mut self, playing: &Station) {
+        if self.history.len() == HISTORY_SIZE {
+            self.history.remove(0);
+        }
+        self.history.push(playing.clone());
+    }
+}
+
+/**
+Station after (or before) the playing one in a playlist, wrapping around
+ */
+fn step_playlist(stations: &[Station], playing: &Station, forward: bool) -> Station {
+    let amount = stations.len();
+    let next = match stations.iter().position(|s| s == playing) {
+        Some(pos) if forward => (pos + 1) % amount,
+        Some(pos) => (pos + amount - 1) % amount,
+        None => 0,
+    };
+    stations.get(next).unwrap_or(playing).clone()
+}
+
+/**
+Any station but the playing one, when there is another
+ */
+fn random_station(list: &[Station], playing: &Station) -> Station {
+    let others: Vec<&Station> = list.iter().filter(|station| *station != playing).collect();
+    match others.len() {
+        0 => playing.clone(),
+        amount => others[random::<usize>() % amount].clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Genre;
+
+    fn genre(id: usize) -> Genre {
+        Genre {
+            id,
+            name: format!("Genre {}", id),
+        }
+    }
+
+    fn station(id: usize, genres: &[usize]) -> Station {
+        let mut station = Station::default();
+        station.id = id;
+        station.genre = genres.iter().map(|id| genre(*id)).collect();
+        station
+    }
+
+    fn ids(stations: &[Station]) -> Vec<usize> {
+        stations.iter().map(|station| station.id).collect()
+    }
+
+    #[test]
+    fn favorites_wrap_around() {
+        let list: Vec<Station> = (1..=5).map(|id| station(id, &[])).collect();
+        let favorites = vec![list[1].clone(), list[3].clone()];
+        let mut navigation = Navigation::new(NextPolicy::Favorites);
+        let second = navigation.next(&list, &favorites, &list[1]);
+        assert_eq!(second.id, 4);
+        let first = navigation.next(&list, &favorites, &second);
+        assert_eq!(first.id, 2);
+    }
+
+    #[test]
+    fn genre_wraps_around_the_stations_of_the_genre() {
+        let list = vec![
+            station(1, &[1]),
+            station(2, &[2]),
+            station(3, &[1, 2]),
+            station(4, &[3]),
+        ];
+        let mut navigation = Navigation::new(NextPolicy::Genre);
+        let mut playing = list[0].clone();
+        let mut played = Vec::new();
+        for _ in 0..3 {
+            playing = navigation.next(&list, &[], &playing);
+            played.push(playing.clone());
+        }
+        assert_eq!(ids(&played), [3, 1, 3]);
+    }
+
+    #[test]
+    fn previous_goes_back_through_the_history() {
+        let list: Vec<Station> = (1..=3).map(|id| station(id, &[])).collect();
+        let mut navigation = Navigation::new(NextPolicy::Favorites);
+        let second = navigation.next(&list, &list, &list[0]);
+        let third = navigation.next(&list, &list, &second);
+        assert_eq!(navigation.previous(&third).map(|s| s.id), Some(2));
+        assert_eq!(navigation.previous(&second).map(|s| s.id), Some(1));
+        // the history is empty, without playlist there is nothing before
+        assert_eq!(navigation.previous(&list[0]), None);
+    }
+
+    #[test]
+    fn previous_steps_back_in_the_playlist_once_the_history_is_empty() {
+        let list: Vec<Station> = (1..=3).map(|id| station(id, &[])).collect();
+        let mut navigation = Navigation::new(NextPolicy::Random);
+        let first = navigation
+            .activate(Playlist::Favorites, list.clone(), &list[1])
+            .unwrap();
+        assert_eq!(first.id, 1);
+        assert_eq!(navigation.playlist(), Some(&Playlist::Favorites));
+        // back to the station played before the playlist
+        assert_eq!(navigation.previous(&first).map(|s| s.id), Some(2));
+        // then around the playlist
+        assert_eq!(navigation.previous(&first).map(|s| s.id), Some(3));
+    }
+
+    #[test]
+    fn station_missing_from_its_playlist() {
+        let list: Vec<Station> = (1..=3).map(|id| station(id, &[])).collect();
+        let outside = station(9, &[]);
+        let mut navigation = Navigation::new(NextPolicy::Random);
+        navigation.activate(Playlist::Favorites, list.clone(), &outside);
+        navigation.previous(&outside);
+
+        // the playlist starts over from its first station
+        assert_eq!(navigation.next(&list, &[], &outside).id, 1);
+        navigation.previous(&outside);
+        assert_eq!(navigation.previous(&outside).map(|s| s.id), Some(1));
+        // an empty playlist keeps the playing station
+        navigation.activate(Playlist::Favorites, Vec::new(), &outside);
+        assert_eq!(navigation.next(&list, &[], &outside).id, 9);
+    }
+
+    #[test]
+    fn random_never_plays_the_same_station_again() {
+        let list: Vec<Station> = (1..=3).map(|id| station(id, &[])).collect();
+        let mut navigation = Navigation::new(NextPolicy::Random);
+        for _ in 0..20 {
+            assert_ne!(navigation.next(&list, &[], &list[0]).id, 1);
+        }
+        let alone = vec![list[0].clone()];
+        assert_eq!(navigation.next(&alone, &[], &list[0]).id, 1);
+    }
+}
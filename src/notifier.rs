@@ -0,0 +1,274 @@
+use crate::api::Station;
+use crate::config::Config;
+use crate::tools::icon_path;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use zbus::zvariant::Value;
+use zbus::{proxy, Connection};
+
+const APP_NAME: &str = "rrt";
+/// Time the notifications stay on screen, in milliseconds
+const EXPIRE: i32 = 5000;
+
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications",
+    gen_blocking = false
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+struct Notification {
+    summary: String,
+    body: String,
+    icon: String,
+}
+
+/**
+Last notification sent, and the one waiting for the interval to be over
+ */
+#[derive(Default)]
+struct Throttle {
+    /// id of the notification shown, replaced by the next one
+    id: u32,
+    sent: Option<Instant>,
+    pending: Option<Notification>,
+}
+
+/**
+Send desktop notifications through the session bus. They are sent at most once per interval,
+only the latest one is shown when they come faster
+ */
+pub struct Notifier {
+    proxy: NotificationsProxy<'static>,
+    handle: Handle,
+    interval: Duration,
+    throttle: Arc<Mutex<Throttle>>,
+}
+
+impl Notifier {
+    /**
+    Connect to the notification daemon, None if the notifications are disabled or unavailable
+     */
+    pub async fn new(config: &Config) -> Option<Self> {
+        if !config.notifications {
+            return None;
+        }
+        let connection = Connection::session().await.ok()?;
+        Self::with_connection(config, &connection).await
+    }
+
+    /**
+    Send the notifications on a given bus, None if the notifications are disabled
+     */
+    pub async fn with_connection(config: &Config, connection: &Connection) -> Option<Self> {
+        if !config.notifications {
+            return None;
+        }
+        let proxy = NotificationsProxy::new(connection).await.ok()?;
+        Some(Self {
+            proxy,
+            handle: Handle::current(),
+            interval: Duration::from_secs(config.notification_interval),
+            throttle: Arc::new(Mutex::new(Throttle::default())),
+        })
+    }
+
+    /**
+    Notify a change on the station, shown with its icon. Never blocks, even outside of the async runtime
+     */
+    pub fn notify(&self, summary: &str, body: &str, station: &Station) {
+        let notification = Notification {
+            summary: summary.to_string(),
+            body: body.to_string(),
+            icon: icon_path(station)
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+        };
+
+        let wait = {
+            let mut throttle = self.throttle.lock().unwrap();
+            // a notification is already waiting, it is replaced by this one
+            if throttle.pending.replace(notification).is_some() {
+                return;
+            }
+            throttle.sent.map_or(Duration::ZERO, |sent| {
+                self.interval.saturating_sub(sent.elapsed())
+            })
+        };
+
+        let proxy = self.proxy.clone();
+        let throttle = self.throttle.clone();
+        self.handle.spawn(async move {
+            tokio::time::sleep(wait).await;
+            let (notification, id) = {
+                let mut throttle = throttle.lock().unwrap();
+                throttle.sent = Some(Instant::now());
+                (throttle.pending.take(), throttle.id)
+            };
+            if let Some(notification) = notification {
+                if let Ok(id) = proxy
+                    .notify(
+                        APP_NAME,
+                        id,
+                        &notification.icon,
+                        &notification.summary,
+                        &notification.body,
+                        &[],
+                        HashMap::new(),
+                        EXPIRE,
+                    )
+                    .await
+                {
+                    throttle.lock().unwrap().id = id;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mpris::tests::TestBus;
+    use zbus::interface;
+    use zbus::zvariant::OwnedValue;
+
+    /// Notify calls received, as (replaces_id, summary, body)
+    type Calls = Arc<Mutex<Vec<(u32, String, String)>>>;
+
+    /**
+    Notification daemon keeping the calls it receives, the id of a replaced notification is kept
+     */
+    struct FakeNotifications {
+        calls: Calls,
+        last_id: u32,
+    }
+
+    #[interface(name = "org.freedesktop.Notifications")]
+    impl FakeNotifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &mut self,
+            _app_name: String,
+            replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            body: String,
+            _actions: Vec<String>,
+            _hints: HashMap<String, OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((replaces_id, summary, body));
+            if replaces_id != 0 {
+                return replaces_id;
+            }
+            self.last_id += 1;
+            self.last_id
+        }
+    }
+
+    /**
+    Notification daemon served on the bus, with the calls it receives
+     */
+    async fn serve(bus: &TestBus) -> (Connection, Calls) {
+        let calls = Calls::default();
+        let connection = bus.connect().await;
+        let daemon = FakeNotifications {
+            calls: calls.clone(),
+            last_id: 41,
+        };
+        connection
+            .object_server()
+            .at("/org/freedesktop/Notifications", daemon)
+            .await
+            .unwrap();
+        connection
+            .request_name("org.freedesktop.Notifications")
+            .await
+            .unwrap();
+        (connection, calls)
+    }
+
+    /**
+    Calls received once there are `amount` of them
+     */
+    async fn received(calls: &Calls, amount: usize) -> Vec<(u32, String, String)> {
+        for _ in 0..100 {
+            if calls.lock().unwrap().len() >= amount {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        calls.lock().unwrap().clone()
+    }
+
+    fn config(notifications: bool) -> Config {
+        Config {
+            notifications,
+            notification_interval: 1,
+            ..Config::default()
+        }
+    }
+
+    fn call(replaces_id: u32, summary: &str, body: &str) -> (u32, String, String) {
+        (replaces_id, summary.to_string(), body.to_string())
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let bus = TestBus::start();
+        let client = bus.connect().await;
+        assert!(Notifier::with_connection(&config(false), &client)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn notify_replaces_the_last_one_and_throttles() {
+        let bus = TestBus::start();
+        let (_daemon, calls) = serve(&bus).await;
+        let client = bus.connect().await;
+        let notifier = Notifier::with_connection(&config(true), &client)
+            .await
+            .unwrap();
+        let station = Station::default();
+
+        notifier.notify("Armin van Buuren - Blah Blah Blah", "Record", &station);
+        assert_eq!(
+            received(&calls, 1).await,
+            [call(0, "Armin van Buuren - Blah Blah Blah", "Record")]
+        );
+
+        // within the interval, only the latest one is sent, in place of the first one
+        notifier.notify("Second", "Record", &station);
+        notifier.notify("Third", "Record", &station);
+        assert_eq!(received(&calls, 2).await.len(), 2);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(
+            calls.lock().unwrap()[..],
+            [
+                call(0, "Armin van Buuren - Blah Blah Blah", "Record"),
+                call(42, "Third", "Record")
+            ]
+        );
+    }
+}
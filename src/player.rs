@@ -1,171 +1,173 @@
+use crate::api::Title;
+use crate::backend::{new_backend, AudioBackend, Backend, SharedState};
+use crate::config::Config;
 use crossbeam::channel;
-use crossbeam::channel::{Receiver, Sender};
-use std::thread;
+use crossbeam::channel::Receiver;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
 
-#[cfg(feature = "libmpv_player")]
-use libmpv::{FileState, Mpv};
-
-#[cfg(feature = "rodio_player")]
-use {
-    curl::easy::Easy,
-    rodio::{Decoder, OutputStream, Source},
-    std::fs::File,
-    std::io::{BufReader, BufWriter, Write},
-    std::sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+/// Events waiting for the app, newer ones are dropped while it is full
+const EVENTS: usize = 32;
+
+/**
+State of the playback, updated by the backend from what really happens to the stream
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackState {
+    Stopped,
+    /// The stream is opened but nothing is heard yet
+    Buffering,
+    Playing,
+    Paused,
+    Reconnecting {
+        attempt: u32,
+        max: u32,
     },
-};
+    Error(String),
+}
 
-#[cfg(feature = "rodio_player")]
-const TEMPFILE: &str = "rrsound";
+impl PlaybackState {
+    /**
+    The stream is wanted, even if nothing is heard yet
+     */
+    pub fn is_playing(&self) -> bool {
+        matches!(
+            self,
+            PlaybackState::Buffering | PlaybackState::Playing | PlaybackState::Reconnecting { .. }
+        )
+    }
 
-enum PlayerCommand {
-    Play(String),
-    Stop,
-    NowPlaying,
+    /**
+    Status as named by MPRIS
+     */
+    pub fn mpris_status(&self) -> &'static str {
+        match self {
+            PlaybackState::Paused => "Paused",
+            state if state.is_playing() => "Playing",
+            _ => "Stopped",
+        }
+    }
+
+    /**
+    Title shown to MPRIS clients, replaced by the state of the stream while nothing is heard
+     */
+    pub fn mpris_title(&self, title: &str) -> String {
+        match self {
+            PlaybackState::Playing | PlaybackState::Paused => title.to_string(),
+            PlaybackState::Error(error) => error.clone(),
+            state => state.to_string(),
+        }
+    }
+
+    /**
+    Track shown to MPRIS clients, none while nothing is heard
+     */
+    pub fn mpris_track(&self, track: Option<&Title>) -> Option<Title> {
+        match self {
+            PlaybackState::Playing | PlaybackState::Paused => track.cloned(),
+            _ => None,
+        }
+    }
 }
 
-enum PlayerResponse {
-    NowPlaying(String),
+impl Display for PlaybackState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlaybackState::Stopped => write!(f, "Stopped"),
+            PlaybackState::Buffering => write!(f, "Buffering"),
+            PlaybackState::Playing => write!(f, "Now playing"),
+            PlaybackState::Paused => write!(f, "Paused"),
+            PlaybackState::Reconnecting { attempt, max } => {
+                write!(f, "Reconnecting ({}/{})", attempt, max)
+            }
+            PlaybackState::Error(_) => write!(f, "Error"),
+        }
+    }
+}
+
+/**
+Change reported by the backend to the app
+ */
+#[derive(Debug, Clone)]
+pub enum PlayerEvent {
+    State(PlaybackState),
+    /// Title sent in the stream metadata
+    Title(String),
+    Volume(u8),
 }
 
 pub struct Player {
-    playing: bool,
     url: String,
-    sender: Sender<PlayerCommand>,
-    receiver: Receiver<PlayerResponse>,
+    backend: Box<dyn AudioBackend>,
+    state: SharedState,
+    events: Receiver<PlayerEvent>,
+    volume: u8,
+    muted: bool,
 }
 
 /**
 Player used to control the station playback
  */
 impl Player {
-    pub fn new(url: String) -> Self {
-        let (sender_player, receiver_player) = channel::bounded(1);
-        let (sender_interface, receiver_interface) = channel::bounded(1);
-
-        #[cfg(feature = "libmpv_player")]
-        thread::spawn(move || {
-            let mpv = Mpv::new().unwrap();
-            mpv.set_property("volume", 85).unwrap();
-            mpv.set_property("vo", "null").unwrap();
-
-            loop {
-                if receiver_player.is_empty() {
-                    thread::sleep(Duration::from_millis(200));
-                    continue;
-                }
-                match receiver_player.recv().unwrap() {
-                    PlayerCommand::Play(url) => {
-                        mpv.playlist_load_files(&[(&url, FileState::Replace, None)])
-                            .unwrap();
-                        mpv.unpause().unwrap();
-                    }
-                    PlayerCommand::Stop => {
-                        mpv.playlist_clear().unwrap();
-                        mpv.pause().unwrap()
-                    }
-                    PlayerCommand::NowPlaying => {
-                        let title = if let Ok(title) = mpv.get_property::<String>("media-title") {
-                            title
-                        } else {
-                            "Loading...".to_string()
-                        };
-                        sender_interface
-                            .send(PlayerResponse::NowPlaying(title))
-                            .unwrap();
-                    }
-                };
-            }
-        });
-
-        #[cfg(feature = "rodio_player")]
-        thread::spawn(move || {
-            let mut path = std::env::temp_dir();
-            path.push(TEMPFILE);
-            let playing = Arc::new(AtomicBool::new(false));
-
-            loop {
-                if receiver_player.is_empty() {
-                    thread::sleep(Duration::from_millis(200));
-                    continue;
-                }
-                match receiver_player.recv().unwrap() {
-                    PlayerCommand::Play(url) => {
-                        // write to tempfile
-                        let mut file = BufWriter::new(File::create(&path).unwrap());
-                        let mut easy = Easy::new();
-                        easy.write_function(move |data| {
-                            file.write_all(data).unwrap();
-                            Ok(data.len())
-                        })
-                        .unwrap();
-
-                        let playing_ = playing.clone();
-                        easy.progress_function(move |_, _, _, _| playing_.load(Ordering::Acquire))
-                            .unwrap();
-                        easy.progress(true).unwrap();
-                        easy.url(url.as_str()).unwrap();
-                        playing.store(true, Ordering::Release);
-
-                        thread::spawn(move || easy.perform());
-
-                        // read from tempfile
-                        let source = loop {
-                            if let Ok(source) = Decoder::new(BufReader::new(
-                                File::open(&path).expect("file not found"),
-                            )) {
-                                break source;
-                            };
-                        };
-
-                        let playing_ = playing.clone();
-                        thread::spawn(move || {
-                            let (_stream, handle) =
-                                OutputStream::try_default().expect("no output found");
-                            let _res = handle.play_raw(source.convert_samples());
-
-                            loop {
-                                if !playing_.load(Ordering::Acquire) {
-                                    break;
-                                }
-                                thread::sleep(Duration::from_millis(200));
-                            }
-                        });
-                    }
-                    PlayerCommand::Stop => {
-                        playing.store(false, Ordering::Relaxed);
-                    }
-                    PlayerCommand::NowPlaying => {
-                        sender_interface
-                            .send(PlayerResponse::NowPlaying("Not implemented".to_string()))
-                            .unwrap();
-                    }
-                }
-            }
-        });
+    pub fn new(url: String, config: &Config, backend: Backend) -> Self {
+        Self::with_backend(url, config, |state| new_backend(backend, state, config))
+    }
 
+    /**
+    Player on a backend made by the caller from the shared state
+     */
+    pub fn with_backend(
+        url: String,
+        config: &Config,
+        backend: impl FnOnce(SharedState) -> Box<dyn AudioBackend>,
+    ) -> Self {
+        let (sender_events, receiver_events) = channel::bounded(EVENTS);
+        let state = SharedState::new(sender_events);
         Self {
-            playing: false,
             url,
-            sender: sender_player,
-            receiver: receiver_interface,
+            backend: backend(state.clone()),
+            state,
+            events: receiver_events,
+            volume: config.volume.min(100),
+            muted: false,
         }
     }
 
+    /**
+    Changes of the playback, to be received by the app
+     */
+    pub fn events(&self) -> Receiver<PlayerEvent> {
+        self.events.clone()
+    }
+
+    /**
+    Resume a paused stream, or play the last url again
+     */
     pub fn resume(&mut self) {
-        if !self.is_playing() {
-            self.play(&self.url.clone());
+        match self.state() {
+            PlaybackState::Paused => self.backend.resume(),
+            state if !state.is_playing() => {
+                self.play(&self.url.clone());
+            }
+            _ => {}
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if self.is_playing() {
+            self.backend.pause();
         }
     }
 
     pub fn toggle_play(&mut self) {
         if self.is_playing() {
-            self.stop();
+            self.pause();
         } else {
-            self.play(&self.url.clone());
+            self.resume();
         }
     }
 
@@ -173,15 +175,21 @@ impl Player {
     Stop the player
      */
     pub fn stop(&mut self) {
-        self.sender.send(PlayerCommand::Stop).unwrap();
-        self.playing = false;
+        self.backend.stop();
     }
 
     /**
-    Player is playing
+    Player is playing or trying to. A stream that failed is not playing anymore
      */
     pub fn is_playing(&self) -> bool {
-        self.playing
+        self.state().is_playing()
+    }
+
+    /**
+    State of the playback as reported by the backend
+     */
+    pub fn state(&self) -> PlaybackState {
+        self.state.get()
     }
 
     /**
@@ -197,30 +205,57 @@ impl Player {
     }
 
     /**
-    Play the station from the specified url
+    Play the station from the specified url. The state stays Buffering until the backend hears the stream
      */
     pub fn play(&mut self, url: &str) -> bool {
-        if !self.playing {
+        if !self.is_playing() {
             self.url = url.to_string();
-            self.sender
-                .send(PlayerCommand::Play(url.to_string()))
-                .unwrap();
-            self.playing = true;
+            self.backend.play(url);
 
             return true;
         }
         false
     }
 
-    /// The current playing title (author and title name)
-    pub fn now_playing(&self) -> Option<String> {
-        self.sender.send(PlayerCommand::NowPlaying).unwrap();
-        let res = self.receiver.recv().unwrap();
+    /**
+    Set the volume, between 0 and 100
+     */
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(100);
+        self.backend.set_volume(self.volume);
+    }
 
-        if let PlayerResponse::NowPlaying(title) = res {
-            return Some(title);
-        }
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.backend.set_mute(self.muted);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /**
+    Write the played stream to a file as well, stop recording if None
+     */
+    pub fn record(&self, path: Option<PathBuf>) -> io::Result<()> {
+        self.backend.record(path)
+    }
 
-        None
+    /**
+    Time to wait before touching the previous recorded file, the backend may still be closing it
+     */
+    pub fn record_close_delay(&self) -> Duration {
+        self.backend.record_close_delay()
+    }
+
+    /**
+    The current playing title (author and title name), if the backend reads it from the stream
+     */
+    pub fn now_playing(&self) -> Option<String> {
+        self.backend.now_playing()
     }
 }
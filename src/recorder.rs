@@ -0,0 +1,310 @@
+use crate::api::{Station, Title};
+use crate::config::Config;
+use crate::player::Player;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Delay between two checks of the playing track while recording
+pub const TRACK_POLL: Duration = Duration::from_secs(10);
+
+/**
+File being recorded and what is in it
+ */
+struct Segment {
+    path: PathBuf,
+    station: Station,
+    title: Option<Title>,
+}
+
+/**
+Record the stream played by the player to files, optionally one file per track
+ */
+pub struct Recorder {
+    dir: PathBuf,
+    split: bool,
+    segment: Option<Segment>,
+    /// Files being tagged in the background
+    tagging: Vec<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn new(config: &Config) -> Self {
+        let dir = config.record_dir.clone().unwrap_or_else(|| {
+            let mut path = dirs_next::audio_dir()
+                .or_else(dirs_next::home_dir)
+                .unwrap_or_default();
+            path.push("radiorecord");
+            path
+        });
+        Self {
+            dir,
+            split: config.record_split,
+            segment: None,
+            tagging: Vec::new(),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.segment.is_some()
+    }
+
+    /**
+    Start recording the stream of the station to a new file
+     */
+    pub fn start(
+        &mut self,
+        player: &Player,
+        station: &Station,
+        url: &str,
+        title: Option<Title>,
+    ) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(file_name(station, title.as_ref(), url));
+
+        player.record(Some(path.clone()))?;
+        self.segment = Some(Segment {
+            path: path.clone(),
+            station: station.clone(),
+            title,
+        });
+        Ok(path)
+    }
+
+    /**
+    Stop recording. The last file is tagged in the background, once the backend closed it
+     */
+    pub fn stop(&mut self, player: &Player) -> io::Result<()> {
+        let segment = self.segment.take();
+        player.record(None)?;
+        if let Some(segment) = segment {
+            self.tag_later(segment, player.record_close_delay());
+        }
+        Ok(())
+    }
+
+    /**
+    Wait for the recorded files to be tagged, before leaving
+     */
+    pub fn finish(&mut self) {
+        for handle in self.tagging.drain(..) {
+            let _ = handle.join();
+        }
+    }
+
+    /**
+    Tag a file after a delay, without blocking the player
+     */
+    fn tag_later(&mut self, segment: Segment, delay: Duration) {
+        if !is_taggable(&segment.path) {
+            return;
+        }
+        self.tagging.retain(|handle| !handle.is_finished());
+        self.tagging.push(thread::spawn(move || {
+            thread::sleep(delay);
+            let _ = tag_file(&segment);
+        }));
+    }
+
+    /**
+    The playing track changed. Start a new file if splitting is enabled.
+    Return the path of the new file if any
+     */
+    pub fn track_changed(
+        &mut self,
+        player: &Player,
+        url: &str,
+        title: Title,
+    ) -> io::Result<Option<PathBuf>> {
+        let segment = match &mut self.segment {
+            Some(segment) => segment,
+            None => return Ok(None),
+        };
+        // the first known title is the one of the current file
+        if segment.title.is_none() {
+            segment.title = Some(title);
+            return Ok(None);
+        }
+        if !self.split || segment.title.as_ref() == Some(&title) {
+            return Ok(None);
+        }
+
+        let station = segment.station.clone();
+        self.stop(player)?;
+        self.start(player, &station, url, Some(title)).map(Some)
+    }
+
+    /**
+    The player switched to another station (or stream). The backend reopens the file for each stream
+    so a new one is always started
+     */
+    pub fn station_changed(
+        &mut self,
+        player: &Player,
+        station: &Station,
+        url: &str,
+    ) -> io::Result<Option<PathBuf>> {
+        if !self.is_recording() {
+            return Ok(None);
+        }
+        self.stop(player)?;
+        self.start(player, station, url, None).map(Some)
+    }
+}
+
+/**
+Name of a recorded file from the station and the track if known
+ */
+fn file_name(station: &Station, title: Option<&Title>, url: &str) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let name = match title {
+        Some(title) => format!(
+            "{} {} - {} - {}",
+            timestamp, station.title, title.artist, title.song
+        ),
+        None => format!("{} {}", timestamp, station.title),
+    };
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+    format!("{}.{}", name, extension(url))
+}
+
+/**
+Extension of the recorded file depending on the stream format
+ */
+fn extension(url: &str) -> &'static str {
+    let url = url.to_lowercase();
+    if url.contains(".aac") {
+        "aac"
+    } else if url.contains(".m3u8") {
+        "ts"
+    } else {
+        "mp3"
+    }
+}
+
+/**
+An ID3 tag can be put in front of the file. It would break the packets of a MPEG transport stream
+ */
+fn is_taggable(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("mp3" | "aac")
+    )
+}
+
+/**
+Prepend an ID3 tag with the artist, song and station to a recorded file
+ */
+fn tag_file(segment: &Segment) -> io::Result<()> {
+    if !segment.path.exists() {
+        return Ok(());
+    }
+    let (artist, song) = match &segment.title {
+        Some(title) => (title.artist.as_str(), title.song.as_str()),
+        None => ("", ""),
+    };
+    let tag = id3_tag(&[
+        ("TPE1", artist),
+        ("TIT2", song),
+        ("TALB", segment.station.title.as_str()),
+    ]);
+
+    let tmp = segment.path.with_extension("part");
+    {
+        let mut out = BufWriter::new(File::create(&tmp)?);
+        out.write_all(&tag)?;
+        io::copy(&mut BufReader::new(File::open(&segment.path)?), &mut out)?;
+        out.flush()?;
+    }
+    fs::rename(tmp, &segment.path)
+}
+
+/**
+Build an ID3v2.4 tag with utf-8 text frames. Empty frames are skipped
+ */
+fn id3_tag(frames: &[(&str, &str)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (id, text) in frames.iter().filter(|(_, text)| !text.is_empty()) {
+        body.extend_from_slice(id.as_bytes());
+        body.extend_from_slice(&synchsafe(text.len() as u32 + 1));
+        body.extend_from_slice(&[0, 0]);
+        // utf-8 encoding
+        body.push(3);
+        body.extend_from_slice(text.as_bytes());
+    }
+
+    let mut tag = b"ID3".to_vec();
+    tag.extend_from_slice(&[4, 0, 0]);
+    tag.extend_from_slice(&synchsafe(body.len() as u32));
+    tag.extend(body);
+    tag
+}
+
+/**
+Encode a size on 4 bytes of 7 bits as required by ID3
+ */
+fn synchsafe(size: u32) -> [u8; 4] {
+    [
+        (size >> 21 & 0x7f) as u8,
+        (size >> 14 & 0x7f) as u8,
+        (size >> 7 & 0x7f) as u8,
+        (size & 0x7f) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Backend;
+    use std::process;
+
+    /**
+    Record a file from the given stream url, the data being written in place of the backend
+     */
+    fn record(url: &str) -> Vec<u8> {
+        let config = Config {
+            record_dir: Some(std::env::temp_dir().join(format!("rrt-tag-{}", process::id()))),
+            ..Config::default()
+        };
+        let player = Player::new(String::new(), &config, Backend::Null);
+        let mut recorder = Recorder::new(&config);
+        let mut station = Station::default();
+        station.title = "Record".to_string();
+        let title = Title {
+            song: "Blah Blah Blah".to_string(),
+            artist: "Armin van Buuren".to_string(),
+        };
+        let path = recorder.start(&player, &station, url, Some(title)).unwrap();
+        fs::write(&path, b"stream").unwrap();
+        recorder.stop(&player).unwrap();
+        recorder.finish();
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        data
+    }
+
+    #[test]
+    fn mp3_files_are_tagged() {
+        let data = record("https://radiorecord.hostingradio.ru/rr_main96.aacp.mp3");
+        assert!(data.starts_with(b"ID3"));
+        assert!(data.ends_with(b"stream"));
+        assert!(data.windows(14).any(|frame| frame == b"Blah Blah Blah"));
+    }
+
+    #[test]
+    fn transport_streams_are_left_untouched() {
+        let data = record("https://hls-01-radiorecord.hostingradio.ru/record/playlist.m3u8");
+        assert_eq!(data, b"stream");
+    }
+}
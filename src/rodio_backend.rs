@@ -0,0 +1,265 @@
+use crate::backend::{AudioBackend, SharedState};
+use crate::config::Config;
+use crate::icy::IcyParser;
+use crate::player::{PlaybackState, PlayerEvent};
+use crate::stream_buffer::StreamBuffer;
+use curl::easy::{Easy, List};
+use rodio::{Decoder, OutputStream, Sink};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/**
+Backend downloading the stream with curl into a memory buffer, and playing it with rodio
+ */
+pub struct RodioBackend {
+    state: SharedState,
+    /// each stream has its own flag, cleared to stop it
+    playing: Option<Arc<AtomicBool>>,
+    /// bytes downloaded and not decoded yet of the current stream
+    buffer: Option<StreamBuffer>,
+    /// sink of the stream being heard, once enough is downloaded
+    sink: Arc<Mutex<Option<Arc<Sink>>>>,
+    /// volume applied to the sink, 0 when muted
+    sink_volume: Arc<Mutex<f32>>,
+    volume: u8,
+    muted: bool,
+    /// file the downloaded stream is also written to
+    record: Arc<Mutex<Option<BufWriter<File>>>>,
+    /// title sent in the stream metadata
+    title: Arc<Mutex<Option<String>>>,
+}
+
+impl RodioBackend {
+    pub fn new(state: SharedState, config: &Config) -> Self {
+        let volume = config.volume.min(100);
+        Self {
+            state,
+            playing: None,
+            buffer: None,
+            sink: Arc::new(Mutex::new(None)),
+            sink_volume: Arc::new(Mutex::new(volume as f32 / 100.0)),
+            volume,
+            muted: false,
+            record: Arc::new(Mutex::new(None)),
+            title: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /**
+    Stop the download and the output of the current stream
+     */
+    fn stop_stream(&mut self) {
+        if let Some(playing) = self.playing.take() {
+            playing.store(false, Ordering::Release);
+        }
+        if let Some(buffer) = self.buffer.take() {
+            buffer.close();
+        }
+        if let Some(sink) = self.sink.lock().unwrap().take() {
+            sink.stop();
+        }
+    }
+
+    fn apply_volume(&self) {
+        let volume = match self.muted {
+            true => 0.0,
+            false => self.volume as f32 / 100.0,
+        };
+        *self.sink_volume.lock().unwrap() = volume;
+        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+            sink.set_volume(volume);
+        }
+    }
+}
+
+impl AudioBackend for RodioBackend {
+    fn play(&mut self, url: &str) {
+        self.stop_stream();
+        let playing = Arc::new(AtomicBool::new(true));
+        self.playing = Some(playing.clone());
+        let buffer = StreamBuffer::default();
+        self.buffer = Some(buffer.clone());
+        *self.title.lock().unwrap() = None;
+        self.state.set(PlaybackState::Buffering);
+
+        let mut easy = Easy::new();
+        // ask for the titles interleaved in the stream
+        let mut headers = List::new();
+        headers.append("Icy-MetaData: 1").unwrap();
+        easy.http_headers(headers).unwrap();
+
+        let icy = Arc::new(Mutex::new(IcyParser::default()));
+        let icy_ = icy.clone();
+        let buffer_ = buffer.clone();
+        easy.header_function(move |header| {
+            if let Some((name, value)) = String::from_utf8_lossy(header).split_once(':') {
+                let name = name.trim().to_lowercase();
+                if name == "content-type" {
+                    let value = value.split(';').next().unwrap_or_default();
+                    buffer_.set_content_type(value.trim().to_lowercase());
+                } else if name == "icy-metaint" {
+                    icy_.lock().unwrap().set_interval(value);
+                }
+            }
+            true
+        })
+        .unwrap();
+        let buffer_ = buffer.clone();
+        let record = self.record.clone();
+        let title = self.title.clone();
+        let state = self.state.clone();
+        easy.write_function(move |data| {
+            let (audio, new_title) = icy.lock().unwrap().parse(data);
+            if let Some(new_title) = new_title {
+                let mut title = title.lock().unwrap();
+                if title.as_ref() != Some(&new_title) {
+                    *title = Some(new_title.clone());
+                    state.send(PlayerEvent::Title(new_title));
+                }
+            }
+            if let Some(record) = record.lock().unwrap().as_mut() {
+                let _ = record.write_all(&audio);
+            }
+            // a closed buffer aborts the download
+            match buffer_.write(&audio) {
+                true => Ok(data.len()),
+                false => Ok(0),
+            }
+        })
+        .unwrap();
+
+        let progress = playing.clone();
+        easy.progress_function(move |_, _, _, _| progress.load(Ordering::Acquire))
+            .unwrap();
+        easy.progress(true).unwrap();
+        easy.url(url).unwrap();
+
+        let download = playing.clone();
+        let state = self.state.clone();
+        let buffer_ = buffer.clone();
+        thread::spawn(move || {
+            let result = easy.perform();
+            buffer_.close();
+            // a stream stopped on purpose is not an error
+            if download.swap(false, Ordering::AcqRel) {
+                state.set(PlaybackState::Error(match result {
+                    Ok(()) => "The stream ended".to_string(),
+                    Err(error) => error.description().to_string(),
+                }));
+            }
+        });
+
+        let sink = self.sink.clone();
+        let sink_volume = self.sink_volume.clone();
+        let state = self.state.clone();
+        thread::spawn(move || {
+            // the decoder reads the buffer as the stream is downloaded
+            let decoder = match buffer.content_type().as_deref() {
+                Some("audio/mpeg") => Decoder::new_mp3(buffer),
+                Some("audio/aac") | Some("audio/aacp") => Decoder::new_aac(buffer),
+                _ => Decoder::new(buffer),
+            };
+            let source = match decoder {
+                Ok(source) => source,
+                Err(_) => {
+                    if playing.swap(false, Ordering::AcqRel) {
+                        state.set(PlaybackState::Error(
+                            "Could not decode the stream".to_string(),
+                        ));
+                    }
+                    return;
+                }
+            };
+
+            let output = OutputStream::try_default()
+                .map_err(|error| error.to_string())
+                .and_then(|(stream, handle)| match Sink::try_new(&handle) {
+                    Ok(sink) => Ok((stream, sink)),
+                    Err(error) => Err(error.to_string()),
+                });
+            let (_stream, new_sink) = match output {
+                Ok((stream, sink)) => (stream, Arc::new(sink)),
+                Err(error) => {
+                    if playing.swap(false, Ordering::AcqRel) {
+                        state.set(PlaybackState::Error(format!("No audio output : {}", error)));
+                    }
+                    return;
+                }
+            };
+            new_sink.set_volume(*sink_volume.lock().unwrap());
+            new_sink.append(source);
+            {
+                // the stream may have been stopped while buffering
+                let mut sink = sink.lock().unwrap();
+                if !playing.load(Ordering::Acquire) {
+                    return;
+                }
+                *sink = Some(new_sink.clone());
+            }
+            state.set(PlaybackState::Playing);
+
+            // the output stream lives until the sink is stopped or runs dry
+            new_sink.sleep_until_end();
+            if playing.swap(false, Ordering::AcqRel) {
+                state.set(PlaybackState::Error(
+                    "Could not decode the stream".to_string(),
+                ));
+            }
+        });
+    }
+
+    fn stop(&mut self) {
+        self.stop_stream();
+        self.state.set(PlaybackState::Stopped);
+    }
+
+    fn pause(&mut self) {
+        if self.state.get() == PlaybackState::Playing {
+            if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+                sink.pause();
+            }
+            self.state.set(PlaybackState::Paused);
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.state.get() == PlaybackState::Paused {
+            if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+                sink.play();
+            }
+            self.state.set(PlaybackState::Playing);
+        }
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        self.volume = volume;
+        self.apply_volume();
+        self.state.send(PlayerEvent::Volume(volume));
+    }
+
+    fn set_mute(&mut self, muted: bool) {
+        self.muted = muted;
+        self.apply_volume();
+    }
+
+    fn record(&self, path: Option<PathBuf>) -> io::Result<()> {
+        let mut record = self.record.lock().unwrap();
+        // the previous file is flushed and closed before returning, it can be tagged at once
+        if let Some(mut previous) = record.take() {
+            previous.flush()?;
+        }
+        *record = match path {
+            Some(path) => Some(BufWriter::new(File::create(path)?)),
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn now_playing(&self) -> Option<String> {
+        self.title.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Bytes downloaded ahead of the decoder, the download waits once they are reached
+const CAPACITY: usize = 512 * 1024;
+/// Bytes kept behind the decoder, so it can seek back while probing the stream
+const SEEK_BACK: usize = 64 * 1024;
+
+#[derive(Default)]
+struct Buffer {
+    data: VecDeque<u8>,
+    /// Position in the stream of the first byte kept
+    start: u64,
+    /// Position of the decoder in the stream
+    position: u64,
+    content_type: Option<String>,
+    /// No more bytes will be written
+    closed: bool,
+}
+
+impl Buffer {
+    fn end(&self) -> u64 {
+        self.start + self.data.len() as u64
+    }
+
+    fn unread(&self) -> usize {
+        self.end().saturating_sub(self.position) as usize
+    }
+
+    /**
+    Forget the bytes too far behind the decoder
+     */
+    fn trim(&mut self) {
+        let behind = self.position.saturating_sub(self.start) as usize;
+        let excess = behind.saturating_sub(SEEK_BACK).min(self.data.len());
+        self.data.drain(..excess);
+        self.start += excess as u64;
+    }
+}
+
+/**
+Ring buffer between the download of a stream and its decoder, the memory used stays bounded.
+The reader waits for the download, the download waits for the reader when the buffer is full
+ */
+#[derive(Clone, Default)]
+pub struct StreamBuffer {
+    inner: Arc<(Mutex<Buffer>, Condvar)>,
+}
+
+impl StreamBuffer {
+    /**
+    Add downloaded bytes, waiting for room in the buffer. False once the buffer is closed
+     */
+    pub fn write(&self, mut bytes: &[u8]) -> bool {
+        let (lock, changed) = &*self.inner;
+        let mut buffer = lock.lock().unwrap();
+        while !bytes.is_empty() {
+            if buffer.closed {
+                return false;
+            }
+            buffer.trim();
+            let room = CAPACITY.saturating_sub(buffer.unread());
+            if room == 0 {
+                buffer = changed.wait(buffer).unwrap();
+                continue;
+            }
+            let (now, later) = bytes.split_at(room.min(bytes.len()));
+            buffer.data.extend(now);
+            bytes = later;
+            changed.notify_all();
+        }
+        true
+    }
+
+    /**
+    The stream is over or stopped, the reader gets the end of the stream once the buffer is read
+     */
+    pub fn close(&self) {
+        let (lock, changed) = &*self.inner;
+        lock.lock().unwrap().closed = true;
+        changed.notify_all();
+    }
+
+    pub fn set_content_type(&self, content_type: String) {
+        self.inner.0.lock().unwrap().content_type = Some(content_type);
+    }
+
+    /**
+    Content type sent with the stream, waiting for the download to start
+     */
+    pub fn content_type(&self) -> Option<String> {
+        let (lock, changed) = &*self.inner;
+        let mut buffer = lock.lock().unwrap();
+        while buffer.end() == 0 && !buffer.closed {
+            buffer = changed.wait(buffer).unwrap();
+        }
+        buffer.content_type.clone()
+    }
+}
+
+impl Read for StreamBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (lock, changed) = &*self.inner;
+        let mut buffer = lock.lock().unwrap();
+        while buffer.unread() == 0 {
+            if buffer.closed {
+                return Ok(0);
+            }
+            buffer = changed.wait(buffer).unwrap();
+        }
+        let offset = (buffer.position - buffer.start) as usize;
+        let count = buf.len().min(buffer.unread());
+        for (byte, value) in buf
+            .iter_mut()
+            .zip(buffer.data.range(offset..offset + count))
+        {
+            *byte = *value;
+        }
+        buffer.position += count as u64;
+        changed.notify_all();
+        Ok(count)
+    }
+}
+
+impl Seek for StreamBuffer {
+    /**
+    Only the bytes still in the buffer, or not downloaded yet, can be reached. The end of a live stream is unknown
+     */
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let mut buffer = self.inner.0.lock().unwrap();
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => buffer.position.checked_add_signed(offset),
+            SeekFrom::End(_) => None,
+        };
+        match target {
+            Some(target) if target >= buffer.start => {
+                buffer.position = target;
+                Ok(target)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "position out of the stream buffer",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Long enough for a thread which is not blocked to be done
+    const WAIT: Duration = Duration::from_millis(100);
+
+    /// Bytes of a stream, telling their position
+    fn stream(start: usize, length: usize) -> Vec<u8> {
+        (start..start + length).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn read(buffer: &mut StreamBuffer, length: usize) -> Vec<u8> {
+        let mut data = vec![0; length];
+        buffer.read_exact(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn read_waits_for_the_download() {
+        let buffer = StreamBuffer::default();
+        let mut reader = buffer.clone();
+        let (done, finished) = mpsc::channel();
+        thread::spawn(move || done.send(read(&mut reader, 4)).unwrap());
+
+        thread::sleep(WAIT);
+        assert!(finished.try_recv().is_err());
+        buffer.write(b"ab");
+        thread::sleep(WAIT);
+        assert!(finished.try_recv().is_err());
+        buffer.write(b"cdef");
+        assert_eq!(finished.recv_timeout(WAIT * 10).unwrap(), b"abcd");
+    }
+
+    #[test]
+    fn write_waits_for_the_reader_once_full() {
+        let mut buffer = StreamBuffer::default();
+        let writer = buffer.clone();
+        let (done, finished) = mpsc::channel();
+        thread::spawn(move || done.send(writer.write(&stream(0, CAPACITY + 10))).unwrap());
+
+        thread::sleep(WAIT);
+        assert!(finished.try_recv().is_err());
+        assert_eq!(buffer.inner.0.lock().unwrap().unread(), CAPACITY);
+        assert_eq!(read(&mut buffer, 10), stream(0, 10));
+        assert!(finished.recv_timeout(WAIT * 10).unwrap());
+        assert_eq!(read(&mut buffer, CAPACITY), stream(10, CAPACITY));
+    }
+
+    #[test]
+    fn seek_back_within_the_kept_bytes() {
+        let mut buffer = StreamBuffer::default();
+        buffer.write(&stream(0, 100_000));
+        read(&mut buffer, 100_000);
+        // the next write forgets what is too far behind the reader
+        buffer.write(&stream(100_000, 10));
+
+        let kept = 100_000 - SEEK_BACK as u64;
+        assert!(buffer.seek(SeekFrom::Start(kept - 1)).is_err());
+        assert!(buffer
+            .seek(SeekFrom::Current(-(SEEK_BACK as i64) - 1))
+            .is_err());
+        assert_eq!(buffer.seek(SeekFrom::Start(kept)).unwrap(), kept);
+        assert_eq!(read(&mut buffer, 10), stream(kept as usize, 10));
+        assert_eq!(buffer.seek(SeekFrom::Current(-10)).unwrap(), kept);
+        assert!(buffer.seek(SeekFrom::End(0)).is_err());
+    }
+
+    #[test]
+    fn seek_ahead_waits_for_the_download() {
+        let mut buffer = StreamBuffer::default();
+        buffer.write(&stream(0, 10));
+        assert_eq!(buffer.seek(SeekFrom::Start(20)).unwrap(), 20);
+        let mut reader = buffer.clone();
+        let (done, finished) = mpsc::channel();
+        thread::spawn(move || done.send(read(&mut reader, 5)).unwrap());
+
+        buffer.write(&stream(10, 10));
+        thread::sleep(WAIT);
+        assert!(finished.try_recv().is_err());
+        buffer.write(&stream(20, 10));
+        assert_eq!(finished.recv_timeout(WAIT * 10).unwrap(), stream(20, 5));
+    }
+
+    #[test]
+    fn close_ends_the_stream() {
+        let mut buffer = StreamBuffer::default();
+        buffer.write(b"abc");
+        buffer.close();
+        // what was downloaded is still read
+        let mut data = Vec::new();
+        buffer.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"abc");
+        assert_eq!(buffer.read(&mut [0; 4]).unwrap(), 0);
+        assert!(!buffer.write(b"d"));
+    }
+
+    #[test]
+    fn close_releases_the_waiting_threads() {
+        let full = StreamBuffer::default();
+        let writer = full.clone();
+        let (written, write_done) = mpsc::channel();
+        thread::spawn(move || {
+            written
+                .send(writer.write(&stream(0, CAPACITY + 1)))
+                .unwrap()
+        });
+
+        let empty = StreamBuffer::default();
+        let mut reader = empty.clone();
+        let (read, read_done) = mpsc::channel();
+        thread::spawn(move || read.send(reader.read(&mut [0; 4]).unwrap()).unwrap());
+        let typed = empty.clone();
+        let (content_type, type_done) = mpsc::channel();
+        thread::spawn(move || content_type.send(typed.content_type()).unwrap());
+
+        thread::sleep(WAIT);
+        assert!(write_done.try_recv().is_err());
+        assert!(read_done.try_recv().is_err());
+        assert!(type_done.try_recv().is_err());
+        full.close();
+        empty.close();
+        // the writer is told the stream is over, the readers get its end
+        assert!(!write_done.recv_timeout(WAIT * 10).unwrap());
+        assert_eq!(read_done.recv_timeout(WAIT * 10).unwrap(), 0);
+        assert_eq!(type_done.recv_timeout(WAIT * 10).unwrap(), None);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// Durations proposed when cycling through the sleep timer
+const PRESETS: [u64; 4] = [15, 30, 60, 90];
+/// Duration of the volume fades
+const FADE: Duration = Duration::from_secs(60);
+
+/**
+Stop the playback after a while, fading the volume out before
+ */
+pub struct SleepTimer {
+    duration: Duration,
+    end: Instant,
+    volume: u8,
+}
+
+impl SleepTimer {
+    /**
+    Start a timer, the volume is the one to fade out from
+     */
+    pub fn new(duration: Duration, volume: u8) -> Self {
+        Self {
+            duration,
+            end: Instant::now() + duration,
+            volume,
+        }
+    }
+
+    /**
+    Next preset after this one, None when the presets are exhausted
+     */
+    pub fn next_preset(timer: Option<&SleepTimer>) -> Option<Duration> {
+        let current = timer.map_or(0, |timer| timer.duration.as_secs() / 60);
+        PRESETS
+            .iter()
+            .find(|&&minutes| minutes > current)
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.end.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    pub fn end(&self) -> Instant {
+        self.end
+    }
+
+    /// Volume to restore once the timer is over
+    pub fn initial_volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Volume chosen by the user while the timer runs
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume;
+    }
+
+    /**
+    Volume to use now, lowered during the last minute
+     */
+    pub fn volume(&self) -> u8 {
+        let remaining = self.remaining();
+        if remaining >= FADE {
+            return self.volume;
+        }
+        (self.volume as f64 * remaining.as_secs_f64() / FADE.as_secs_f64()).round() as u8
+    }
+}
+
+impl Display for SleepTimer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let remaining = self.remaining().as_secs();
+        write!(f, "{:02}:{:02}", remaining / 60, remaining % 60)
+    }
+}
+
+/**
+Raise the volume progressively, used when an alarm starts the playback
+ */
+pub struct FadeIn {
+    start: Instant,
+    volume: u8,
+}
+
+impl FadeIn {
+    /**
+    Start a fade from silence to the given volume
+     */
+    pub fn new(volume: u8) -> Self {
+        Self {
+            start: Instant::now(),
+            volume,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.start.elapsed() >= FADE
+    }
+
+    /**
+    Volume to use now
+     */
+    pub fn volume(&self) -> u8 {
+        let elapsed = self.start.elapsed().min(FADE);
+        (self.volume as f64 * elapsed.as_secs_f64() / FADE.as_secs_f64()).round() as u8
+    }
+}
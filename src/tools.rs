@@ -1,4 +1,4 @@
-use crate::api;
+use crate::api::Station;
 use crate::config::{get_app_config_path, Error};
 use curl::easy::Easy;
 use image::imageops::FilterType;
@@ -8,7 +8,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Read, Write};
+use std::path::PathBuf;
 use std::process::exit;
+use std::time::Duration;
 use std::{fs, io};
 
 const TEMPDIR: &str = "rricons/";
@@ -17,15 +19,12 @@ const ICONFILE: &str = "art.msgpack";
 /**
 Download all stations icons to a file int the tmp folder
  */
-pub fn get_all_icons() {
-    //get all stations
-    let list = api::stations_list().unwrap();
-
+pub fn get_all_icons(list: &[Station]) -> Result<(), Error> {
     let mut path = std::env::temp_dir();
     path.push(TEMPDIR);
 
     if !path.exists() {
-        fs::create_dir_all(&path).unwrap();
+        fs::create_dir_all(&path)?;
     }
 
     //fetch the icons for each station and write it in a tmp folder
@@ -35,15 +34,19 @@ pub fn get_all_icons() {
             continue;
         }
         let mut easy = Easy::new();
-        let mut file = BufWriter::new(File::create(path).unwrap());
+        let mut file = BufWriter::new(File::create(&path).unwrap());
         easy.url(station.icon_fill_white.as_str()).unwrap();
         easy.write_function(move |data| {
             file.write_all(data).unwrap();
             Ok(data.len())
         })
         .unwrap();
-        easy.perform().unwrap();
+        // an unreachable icon is only displayed as missing
+        if easy.perform().is_err() {
+            let _ = fs::remove_file(&path);
+        }
     }
+    Ok(())
 }
 
 pub type StationsArtList = HashMap<String, StationArt>;
@@ -54,12 +57,17 @@ pub struct StationArt {
     pub size_y: u32,
 }
 /**
+Icon of the station downloaded in the tmp folder, if it is still there
+ */
+pub fn icon_path(station: &Station) -> Option<PathBuf> {
+    let mut path = std::env::temp_dir();
+    path.push(format!("{}{}.png", TEMPDIR, station.prefix));
+    path.exists().then_some(path)
+}
+/**
 Convert the icons previously downloaded to ascii art and write it to a file
  */
-pub fn save_station_art() {
-    //get stations list
-    let list = api::stations_list().unwrap();
-
+pub fn save_station_art(list: &[Station]) -> Result<(), Error> {
     // Convert image to dot art
     let mut art_list = HashMap::new();
     for station in list.iter() {
@@ -70,7 +78,10 @@ pub fn save_station_art() {
         //convert icon in different ascii size
         let size = 128;
 
-        let mut img = image::open(path).expect("Can't find image file at");
+        let mut img = match image::open(path) {
+            Ok(img) => img,
+            Err(_) => continue,
+        };
         let (_, mut height) = img.dimensions();
 
         img = img.resize(size, height, FilterType::Lanczos3);
@@ -117,24 +128,25 @@ pub fn save_station_art() {
     art_list.serialize(&mut Serializer::new(&mut buf)).unwrap();
 
     //create and write save file
-    let mut path = get_app_config_path().unwrap();
+    let mut path = get_app_config_path()?;
     path.push(ICONFILE);
-    let mut file = BufWriter::new(File::create(path).unwrap());
-    file.write_all(&buf).unwrap();
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(&buf)?;
+    Ok(())
 }
 
 /**
 Read the file containing ascii art for icons. If it doesnt exist generate it
  */
-pub fn read_icons() -> Result<StationsArtList, Error> {
+pub fn read_icons(list: &[Station]) -> Result<StationsArtList, Error> {
     let mut path = get_app_config_path()?;
     path.push(ICONFILE);
 
     if !path.exists() {
         println!("Downloading icons...");
-        get_all_icons();
+        get_all_icons(list)?;
         println!("Converting icons...");
-        save_station_art();
+        save_station_art(list)?;
     }
 
     let content = fs::read(path)?;
@@ -142,6 +154,39 @@ pub fn read_icons() -> Result<StationsArtList, Error> {
     Ok(parsed)
 }
 /**
+Parse a duration written as seconds or with units, e.g. `90`, `45m` or `1h30m`
+ */
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' => {
+                let amount: u64 = number
+                    .parse()
+                    .map_err(|_| format!("invalid duration: {}", value))?;
+                total += amount
+                    * match c {
+                        'h' => 3600,
+                        'm' => 60,
+                        _ => 1,
+                    };
+                number.clear();
+            }
+            _ => return Err(format!("invalid duration: {}", value)),
+        }
+    }
+    if !number.is_empty() {
+        return Err(format!("missing unit in duration: {}", value));
+    }
+    Ok(Duration::from_secs(total))
+}
+/**
 Wait for the user to press enter
  **/
 pub fn pause() {
@@ -1,3 +1,4 @@
+use chrono::Local;
 use tui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -5,20 +6,23 @@ use tui::{
     text::{Line, Span},
     widgets::{
         canvas::{Canvas, Points},
-        Block, BorderType, Borders, List, ListItem, Paragraph,
+        Block, BorderType, Borders, Cell, Gauge, List, ListItem, Paragraph, Row, Table,
     },
     Frame,
 };
 
-use crate::app::Status;
+use crate::app::{OverviewSort, Status};
+use crate::player::PlaybackState;
 use crate::tools::StationsArtList;
 use crate::{
-    api::Station,
+    api::{Genre, Station},
     app::{App, MenuItem},
 };
 
 //const ACCENT_COLOR:Color = Color::Rgb(255,96,0);
 const ACCENT_COLOR: Color = Color::Yellow;
+/// Tracks shown in the last played panel
+const STATION_HISTORY: usize = 10;
 
 /**
 Display the help menu on the terminal
@@ -32,6 +36,63 @@ pub fn render_help(rect: &mut Frame, _app: &App) {
 
     rect.render_widget(help_paragraph(), chunks[0])
 }
+/**
+Display the genre browser on the terminal
+ */
+pub fn render_genres(rect: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(100)].as_ref())
+        .margin(2)
+        .split(rect.size());
+
+    let list = make_genres_list(app);
+    rect.render_stateful_widget(list, chunks[0], &mut app.genre_list_state);
+}
+/**
+Display the alarms with the next time they ring and the new alarm being typed
+ */
+pub fn render_alarms(rect: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .margin(2)
+        .split(rect.size());
+
+    let list = make_alarms_list(app);
+    rect.render_stateful_widget(list, chunks[0], &mut app.alarm_list_state);
+    rect.render_widget(alarm_input(app), chunks[1]);
+}
+
+/**
+Display the tracks heard recently
+ */
+pub fn render_history(rect: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(100)].as_ref())
+        .margin(2)
+        .split(rect.size());
+
+    let list = make_history_list(app);
+    rect.render_stateful_widget(list, chunks[0], &mut app.history_list_state);
+}
+
+/**
+Display what is playing on every station
+ */
+pub fn render_overview(rect: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .margin(2)
+        .split(rect.size());
+
+    let table = make_overview_table(app);
+    rect.render_stateful_widget(table, chunks[0], &mut app.overview_state);
+    rect.render_widget(overview_filter(app), chunks[1]);
+}
+
 /**
 Display the main menu on the terminal
 
@@ -50,8 +111,17 @@ pub fn render_stations(rect: &mut Frame, app: &mut App) {
     let stations_list_chunks = split_chunk(stations_chunks[0], Direction::Vertical, 30, 70);
 
     //generate the stations lists
-    let list_std = make_std_stations_list(&app.get_stations_list_std(), &app.active_menu_item);
-    let list_fav = make_fav_stations_list(&app.get_stations_list_fav(), &app.active_menu_item);
+    let list_std = make_std_stations_list(
+        &app.get_stations_list_std(),
+        &app.active_menu_item,
+        app.genre_filter.as_ref(),
+        app.catalogue_stale,
+    );
+    let list_fav = make_fav_stations_list(
+        &app.get_stations_list_fav(),
+        &app.active_menu_item,
+        app.genre_filter.as_ref(),
+    );
 
     //add the stations list. Only the active list is navigable
     match app.active_menu_item {
@@ -77,16 +147,25 @@ pub fn render_stations(rect: &mut Frame, app: &mut App) {
         }
     }
 
-    //add the icons and footer
+    //add the icon and the last tracks of the selected station
+    let station_chunks = split_chunk(stations_chunks[1], Direction::Horizontal, 60, 40);
     make_icon(
         rect,
-        &stations_chunks[1],
+        &station_chunks[0],
         &app.icon_list,
         &app.get_selected_station().unwrap_or_default(),
     );
+    rect.render_widget(make_station_history(app), station_chunks[1]);
+
+    let status = app.get_status();
+    let footer_chunks = split_chunk(chunks[2], Direction::Horizontal, 85, 15);
+    rect.render_widget(volume_gauge(&status), footer_chunks[1]);
 
-    let footer = status_bar(app.get_status(), &app.music_title);
-    rect.render_widget(footer, chunks[2]);
+    let footer = match &status.state {
+        PlaybackState::Error(error) => status_bar(status.to_string(), error),
+        _ => status_bar(status.to_string(), &app.music_title),
+    };
+    rect.render_widget(footer, footer_chunks[0]);
 }
 /**
 Split a Rect into two Rect horizontally (20% - 80%)
@@ -130,7 +209,7 @@ fn base_chunk(size: Rect) -> Vec<Rect> {
 /**
 Paragraph displaying currently playing song
  */
-fn status_bar(status: Status, title: &str) -> Paragraph {
+fn status_bar<'a>(status: String, title: &'a str) -> Paragraph<'a> {
     Paragraph::new(title)
         .style(
             Style::default()
@@ -143,10 +222,32 @@ fn status_bar(status: Status, title: &str) -> Paragraph {
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .style(Style::default().fg(Color::Reset))
-                .title(status.to_string()),
+                .title(status),
         )
 }
 /**
+Gauge displaying the player volume
+ */
+fn volume_gauge<'a>(status: &Status) -> Gauge<'a> {
+    let label = match status.muted {
+        true => "muted".to_string(),
+        false => format!("{}%", status.volume),
+    };
+    Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Volume"),
+        )
+        .gauge_style(Style::default().fg(match status.muted {
+            true => Color::DarkGray,
+            false => ACCENT_COLOR,
+        }))
+        .percent(status.volume as u16)
+        .label(label)
+}
+/**
 Paragraph displaying information about current station
  */
 fn info_bar<'a>(app: &App) -> Paragraph<'a> {
@@ -214,10 +315,37 @@ fn help_paragraph<'a>() -> Paragraph<'a> {
             "{:50}{:40}",
             "Add/remove from favorite", "f"
         ))]),
+        Line::from(vec![Span::raw(format!(
+            "{:50}{:40}",
+            "Change stream quality", "b"
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "{:50}{:40}",
+            "Raise/lower volume", "+/-"
+        ))]),
+        Line::from(vec![Span::raw(format!("{:50}{:40}", "Mute/unmute", "m"))]),
+        Line::from(vec![Span::raw(format!(
+            "{:50}{:40}",
+            "Start/stop recording", "R"
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "{:50}{:40}",
+            "Sleep timer (15/30/60/90 min, off)", "s"
+        ))]),
         Line::from(vec![Span::raw(format!(
             "{:50}{:40}",
             "Enter search mode", "/"
         ))]),
+        Line::from(vec![Span::raw(format!("{:50}{:40}", "Browse genres", "g"))]),
+        Line::from(vec![Span::raw(format!("{:50}{:40}", "Manage alarms", "a"))]),
+        Line::from(vec![Span::raw(format!(
+            "{:50}{:40}",
+            "Recently heard", "l"
+        ))]),
+        Line::from(vec![Span::raw(format!(
+            "{:50}{:40}",
+            "Now playing on every station", "o"
+        ))]),
         Line::from(vec![Span::raw(format!(
             "{:50}{:40}",
             "Get current playing song", "n"
@@ -239,30 +367,53 @@ fn help_paragraph<'a>() -> Paragraph<'a> {
     home
 }
 /**
-She standard station list as a List with the correct style to be displayed
+She standard station list as a List with the correct style to be displayed.
+The title warns when the list comes from the offline cache
  */
-fn make_std_stations_list<'a>(stations_list: &[&Station], menu_item: &MenuItem) -> List<'a> {
+fn make_std_stations_list<'a>(
+    stations_list: &[&Station],
+    menu_item: &MenuItem,
+    genre: Option<&Genre>,
+    stale: bool,
+) -> List<'a> {
     let style = match menu_item {
         MenuItem::Standard(_) => Style::default().fg(ACCENT_COLOR),
         _ => Style::default(),
     };
-    make_stations_list(stations_list, "Stations", style)
+    let mut title = list_title("Stations", genre);
+    if stale {
+        title.push_str(" (offline)");
+    }
+    make_stations_list(stations_list, title, style)
 }
 /**
 She favorite station list as a List with the correct style to be displayed
  */
-fn make_fav_stations_list<'a>(stations_list: &[&Station], menu_item: &MenuItem) -> List<'a> {
+fn make_fav_stations_list<'a>(
+    stations_list: &[&Station],
+    menu_item: &MenuItem,
+    genre: Option<&Genre>,
+) -> List<'a> {
     let style = match menu_item {
         MenuItem::Favorite(_) => Style::default().fg(ACCENT_COLOR),
         _ => Style::default(),
     };
-    make_stations_list(stations_list, "Favorites", style)
+    make_stations_list(stations_list, list_title("Favorites", genre), style)
+}
+/**
+Title of a stations list with the genre it is filtered by
+ */
+fn list_title(name: &str, genre: Option<&Genre>) -> String {
+    match genre {
+        Some(genre) => format!("{} [{}]", name, genre.name),
+        None => name.to_string(),
+    }
 }
 
 /**
 Generate the stations list based on the stations names
  */
-fn make_stations_list<'a>(stations_list: &[&Station], title: &'a str, style: Style) -> List<'a> {
+fn make_stations_list<'a>(stations_list: &[&Station], title: String, style: Style) -> List<'a> {
     let stations = Block::default()
         .borders(Borders::ALL)
         .style(Style::default())
@@ -287,6 +438,233 @@ fn make_stations_list<'a>(stations_list: &[&Station], title: &'a str, style: Sty
             .add_modifier(Modifier::BOLD),
     )
 }
+/**
+Generate the genres list with the amount of stations in each of them
+ */
+fn make_genres_list<'a>(app: &App) -> List<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default())
+        .title("Genres (<Enter> to select, <Esc> to quit)")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(ACCENT_COLOR));
+
+    let mut items = vec![ListItem::new(Line::from(vec![Span::raw(format!(
+        "All genres ({})",
+        app.count_stations()
+    ))]))];
+    items.extend(app.get_genres().iter().map(|genre| {
+        ListItem::new(Line::from(vec![Span::raw(format!(
+            "{} ({})",
+            genre.name,
+            app.count_genre(genre)
+        ))]))
+    }));
+
+    List::new(items).block(block).highlight_style(
+        Style::default()
+            .bg(ACCENT_COLOR)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+/**
+Last tracks played on the selected station
+ */
+fn make_station_history<'a>(app: &App) -> List<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Last played")
+        .border_type(BorderType::Rounded);
+
+    let items: Vec<ListItem> = match &app.station_history {
+        None => vec![ListItem::new("Loading...")],
+        Some(Err(error)) => vec![ListItem::new(error.to_string())],
+        Some(Ok(titles)) => titles
+            .iter()
+            .take(STATION_HISTORY)
+            .map(|title| {
+                ListItem::new(vec![
+                    Line::from(Span::styled(
+                        title.artist.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(title.song.clone()),
+                ])
+            })
+            .collect(),
+    };
+
+    List::new(items).block(block)
+}
+
+/**
+Table of the track playing on every station, the sorted column highlighted
+ */
+fn make_overview_table<'a>(app: &App) -> Table<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default())
+        .title("Now playing (<Enter> to play, <s> to sort, </> to filter, <Esc> to quit)")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(ACCENT_COLOR));
+
+    let header = Row::new(
+        [
+            ("Station", OverviewSort::Station),
+            ("Artist", OverviewSort::Artist),
+            ("Song", OverviewSort::Song),
+        ]
+        .map(|(name, sort)| {
+            let style = if app.overview_sort == sort {
+                Style::default()
+                    .fg(ACCENT_COLOR)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().add_modifier(Modifier::BOLD)
+            };
+            Cell::from(name).style(style)
+        }),
+    );
+
+    let rows: Vec<Row> = app
+        .get_overview()
+        .iter()
+        .map(|(station, title)| {
+            let (artist, song) = match title {
+                Some(title) => (title.artist.clone(), title.song.clone()),
+                None => (String::new(), String::new()),
+            };
+            Row::new(vec![station.title.clone(), artist, song])
+        })
+        .collect();
+
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+            Constraint::Percentage(40),
+        ],
+    )
+    .header(header)
+    .block(block)
+    .highlight_style(
+        Style::default()
+            .bg(ACCENT_COLOR)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+/**
+Filter typed on the overview, or the error of the last refresh
+ */
+fn overview_filter<'a>(app: &App) -> Paragraph<'a> {
+    let (text, title) = match &app.overview_error {
+        Some(error) if !app.overview_filtering => (error.to_string(), "Error"),
+        _ => (app.overview_filter.value().to_string(), "Filter"),
+    };
+    Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(if app.overview_filtering {
+                ACCENT_COLOR
+            } else {
+                Color::Reset
+            })),
+    )
+}
+
+/**
+List of the tracks heard with their station, the most recent first
+ */
+fn make_history_list<'a>(app: &App) -> List<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default())
+        .title("Recently heard (<Esc> to quit)")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(ACCENT_COLOR));
+
+    let items: Vec<ListItem> = app
+        .history
+        .recent()
+        .iter()
+        .map(|track| {
+            ListItem::new(Line::from(vec![Span::raw(format!(
+                "{:20}{:30}{} - {}",
+                track.start.format("%Y-%m-%d %H:%M"),
+                app.station_title(track.station),
+                track.artist,
+                track.song
+            ))]))
+        })
+        .collect();
+
+    List::new(items).block(block).highlight_style(
+        Style::default()
+            .bg(ACCENT_COLOR)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+/**
+List of the alarms with their next ring
+ */
+fn make_alarms_list<'a>(app: &App) -> List<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default())
+        .title("Alarms (<n> to add, <d> to delete, <Esc> to quit)")
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(ACCENT_COLOR));
+
+    let now = Local::now();
+    let items: Vec<ListItem> = app
+        .alarms
+        .iter()
+        .map(|alarm| {
+            let next = match alarm.next(now) {
+                Some(at) => at.format("next on %a %d %b at %H:%M").to_string(),
+                None => "passed".to_string(),
+            };
+            ListItem::new(Line::from(vec![Span::raw(format!("{:40}{}", alarm, next))]))
+        })
+        .collect();
+
+    List::new(items).block(block).highlight_style(
+        Style::default()
+            .bg(ACCENT_COLOR)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+/**
+Input of a new alarm, or the error of the last one typed
+ */
+fn alarm_input<'a>(app: &App) -> Paragraph<'a> {
+    let (text, title) = match (&app.alarm_input, &app.alarm_error) {
+        (Some(input), _) => (
+            input.value().to_string(),
+            "New alarm for the selected station : HH:MM [mon,tue,...|daily|weekdays|weekend]",
+        ),
+        (None, Some(error)) => (error.clone(), "Error"),
+        (None, None) => (String::new(), "Press <n> to add an alarm"),
+    };
+    Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(ACCENT_COLOR)),
+    )
+}
+
 /**
 Canvas with the stations icon
  */